@@ -0,0 +1,24 @@
+use lambda_config::bucket;
+pub use lambda_config::ConfigError;
+
+// Cleanup configuration, loaded and validated once at cold start
+#[derive(Debug)]
+pub struct Config {
+    pub video_bucket: String,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Config, ConfigError> {
+        dotenv::dotenv().ok();
+        Config::from_lookup(|name| dotenv::var(name).ok())
+    }
+
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Config, ConfigError> {
+        let mut errors: Vec<String> = vec![];
+        let video_bucket = bucket(&lookup, "VIDEO_BUCKET", &mut errors);
+        if !errors.is_empty() {
+            return Err(ConfigError { errors });
+        }
+        Ok(Config { video_bucket })
+    }
+}
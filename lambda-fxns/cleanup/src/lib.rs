@@ -1,5 +1,8 @@
 use aws_sdk_s3::{Client, Error};
 use aws_config::BehaviorVersion;
+use serde::Deserialize;
+
+pub mod config;
 
 pub struct DeleteResponse {
     pub key: String,
@@ -35,4 +38,71 @@ pub async fn delete_video(client: &Client, bucket: &str, key: &str) -> Result<De
             })
         }
     }
-}
\ No newline at end of file
+}
+
+// Release the pipeline lock so the next batch can be triggered. Deleting a missing key is a no-op
+pub async fn release_lock(client: &Client, bucket: &str, lock_key: &str) -> Result<(), Error> {
+    client.delete_object()
+        .bucket(bucket)
+        .key(lock_key)
+        .send()
+        .await?;
+    Ok(())
+}
+
+// Where the Map state's ResultWriter put the results i.e.
+// {"ResultWriterDetails": {"Bucket": "videos", "Key": "transcriber-results/<map run id>/manifest.json"}}
+#[derive(Deserialize)]
+pub struct MapResults {
+    #[serde(rename = "ResultWriterDetails")]
+    pub writer: ResultLocation,
+}
+
+#[derive(Deserialize)]
+pub struct ResultLocation {
+    #[serde(rename = "Bucket")]
+    pub bucket: String,
+    #[serde(rename = "Key")]
+    pub key: String,
+}
+
+#[derive(Deserialize)]
+struct ResultFile {
+    #[serde(rename = "Key")]
+    key: String,
+}
+
+#[derive(Deserialize)]
+struct ResultFiles {
+    #[serde(rename = "SUCCEEDED", default)]
+    succeeded: Vec<ResultFile>,
+}
+
+#[derive(Deserialize)]
+struct ResultManifest {
+    #[serde(rename = "ResultFiles")]
+    result_files: ResultFiles,
+}
+
+#[derive(Deserialize)]
+struct ChildExecution {
+    #[serde(rename = "Output")]
+    output: Option<String>,
+}
+
+// Result files of the child executions that succeeded, listed in the ResultWriter manifest.json
+pub fn succeeded_files(manifest: &[u8]) -> Result<Vec<String>, serde_json::Error> {
+    let manifest: ResultManifest = serde_json::from_slice(manifest)?;
+    Ok(manifest.result_files.succeeded.into_iter().map(|f| f.key).collect())
+}
+
+// Output of every child execution in a result file, each the JSON of one transcriber batch
+pub fn execution_outputs(results: &[u8]) -> Result<Vec<String>, serde_json::Error> {
+    let executions: Vec<ChildExecution> = serde_json::from_slice(results)?;
+    Ok(executions.into_iter().filter_map(|e| e.output).collect())
+}
+
+pub async fn get_object_bytes(client: &Client, bucket: &str, key: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let resp = client.get_object().bucket(bucket).key(key).send().await?;
+    Ok(resp.body.collect().await?.into_bytes().to_vec())
+}
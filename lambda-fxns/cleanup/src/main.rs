@@ -1,12 +1,46 @@
 use serde::{Deserialize, Serialize};
+use aws_sdk_s3::Client;
 use lambda_runtime::{run, service_fn, tracing, Error, LambdaEvent};
-use cleanup::{init_s3client, delete_video};
+use cleanup::config::Config;
+use cleanup::{init_s3client, delete_video, execution_outputs, get_object_bytes, release_lock, succeeded_files, MapResults};
+
+// Per-item outcome reported by the transcriber, as much of it as cleanup acts on
+#[derive(Deserialize)]
+struct ItemOutcome {
+    key: String,
+    status: String,
+    error_stage: Option<String>,
+    message: Option<String>,
+}
 
 #[derive(Deserialize)]
 struct TranscriberDetails {
-    message: String,
-    processed: Vec<String>,
-    failed: Vec<String>,
+    items: Vec<ItemOutcome>,
+}
+
+// Video left in the bucket and why i.e. {"key": "week1/video0.mp4", "status": "decoded", "reason": "transcribe: whisper exited with ..."}
+#[derive(Serialize)]
+struct KeptVideo {
+    key: String,
+    status: String,
+    reason: Option<String>,
+}
+
+// Error caught by the state machine i.e. {"Error": "States.Timeout", "Cause": "..."}
+#[derive(Deserialize)]
+struct RunError {
+    #[serde(rename = "Error")]
+    error: String,
+}
+
+// Step Function execution input with where the Map state wrote its results, or the error that stopped the run
+#[derive(Deserialize)]
+struct CleanupEvent {
+    bucket: String,
+    run_id: String,
+    lock_key: Option<String>,
+    results: Option<MapResults>,
+    error: Option<RunError>,
 }
 
 #[derive(Serialize)]
@@ -14,25 +48,65 @@ struct CleanupResponse {
     message: String,
     processed: Vec<String>,
     failed: Vec<String>,
+    kept: Vec<KeptVideo>,
 }
 
 
-#[tracing::instrument(skip(event), fields(req_id = %event.context.request_id))]
-async fn function_handler(event: LambdaEvent<Vec<TranscriberDetails>>) -> Result<CleanupResponse, Error> {
-    dotenv::dotenv().ok();
-    let video_bucket = dotenv::var("VIDEO_BUCKET").expect("VIDEO_BUCKET not set");
+// Every transcriber batch of the run, read back from the files the Map state's ResultWriter put in S3
+async fn load_results(client: &Client, results: &MapResults) -> Result<Vec<TranscriberDetails>, Error> {
+    let location = &results.writer;
+    let manifest = get_object_bytes(client, &location.bucket, &location.key).await?;
+    let mut details: Vec<TranscriberDetails> = vec![];
+    for key in succeeded_files(&manifest)? {
+        let file = get_object_bytes(client, &location.bucket, &key).await?;
+        for output in execution_outputs(&file)? {
+            details.push(serde_json::from_str(&output)?);
+        }
+    }
+    tracing::info!("Loaded {} batch results from {}", details.len(), location.key);
+    Ok(details)
+}
+
+#[tracing::instrument(skip(config, event), fields(req_id = %event.context.request_id))]
+async fn function_handler(config: &Config, event: LambdaEvent<CleanupEvent>) -> Result<CleanupResponse, Error> {
     // Process event payload
-    let items = event.payload;
+    let run = event.payload;
+    let video_bucket = run.bucket;
+    // Only ever delete from the configured video bucket
+    if video_bucket != config.video_bucket {
+        tracing::error!(bucket = %video_bucket, expected = %config.video_bucket, "Refusing cleanup of unexpected bucket");
+        return Err(format!("ERROR: {} is not the configured VIDEO_BUCKET", video_bucket).into());
+    }
     // Init s3 client
-    let s3client = init_s3client().await.unwrap();
+    let s3client = init_s3client().await?;
+    // A failed run only hands back its lock, no video is deleted
+    let items = match (&run.error, &run.results) {
+        (Some(error), _) => {
+            tracing::error!(run_id = %run.run_id, error = %error.error, "Run failed, releasing lock only");
+            vec![]
+        }
+        (None, Some(results)) => load_results(&s3client, results).await?,
+        (None, None) => return Err(format!("ERROR: Run {} has no Map results", run.run_id).into()),
+    };
     // Cleanup
     let mut processed_videos: Vec<String> = vec![];
     let mut failed_videos: Vec<String> = vec![];
+    let mut kept_videos: Vec<KeptVideo> = vec![];
     for item in items {
-        // for processed transcripts only
-        for transcript in item.processed {
-            // change extension from .txt to .mp4
-            let key = transcript.replace(".txt", ".mp4");
+        // Only videos with every format uploaded, now or by an earlier run, are deleted, by the key they were listed under
+        let (uploaded, kept): (Vec<ItemOutcome>, Vec<ItemOutcome>) = item.items.into_iter().partition(|o| o.status == "uploaded" || o.status == "current");
+        kept_videos.extend(kept.into_iter().map(|o| KeptVideo {
+            reason: match (o.error_stage, o.message) {
+                (Some(stage), Some(message)) => Some(format!("{}: {}", stage, message)),
+                (stage, message) => message.or(stage),
+            },
+            key: o.key,
+            status: o.status,
+        }));
+        let mut keys: Vec<String> = uploaded.into_iter().map(|o| o.key).collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
             // delete videos
             match delete_video(&s3client, &video_bucket, &key).await {
                 Ok(resp) => {
@@ -49,11 +123,22 @@ async fn function_handler(event: LambdaEvent<Vec<TranscriberDetails>>) -> Result
             }
         }
     }
+    // Release pipeline lock
+    if let Some(lock_key) = run.lock_key {
+        match release_lock(&s3client, &video_bucket, &lock_key).await {
+            Ok(_) => tracing::info!("Released {}", lock_key),
+            Err(e) => tracing::error!("ERROR: Failed to release {}: {}", lock_key, e),
+        }
+    }
     // Prepare the response
     let resp = CleanupResponse {
-        message: format!("CLEANUP COMPLETE: {}", video_bucket),
+        message: match &run.error {
+            Some(error) => format!("RUN FAILED: {} ({}): {}", video_bucket, run.run_id, error.error),
+            None => format!("CLEANUP COMPLETE: {} ({})", video_bucket, run.run_id),
+        },
         processed: processed_videos,
-        failed: failed_videos
+        failed: failed_videos,
+        kept: kept_videos,
     };
 
     Ok(resp)
@@ -68,5 +153,14 @@ async fn main() -> Result<(), Error> {
         .with_target(false)
         .init();
 
-    run(service_fn(function_handler)).await
+    // Load config once per cold start
+    let config = match Config::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!(errors = ?e.errors, "Invalid configuration");
+            return Err(e.into());
+        }
+    };
+    let config = &config;
+    run(service_fn(move |event| async move { function_handler(config, event).await })).await
 }
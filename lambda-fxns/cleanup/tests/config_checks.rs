@@ -0,0 +1,22 @@
+use cleanup::config::Config;
+use lambda_config::testing::lookup;
+
+fn load(vars: &[(&str, &str)]) -> Result<Config, Vec<String>> {
+    Config::from_lookup(lookup(vars)).map_err(|e| e.errors)
+}
+
+#[test]
+fn load_cleanup_config() {
+    let config = load(&[("VIDEO_BUCKET", "videos")]).unwrap();
+    assert_eq!(config.video_bucket, "videos");
+}
+
+#[test]
+fn report_every_config_error() {
+    // Case 0: Missing bucket
+    assert_eq!(load(&[]).unwrap_err(), vec!["VIDEO_BUCKET not set"]);
+
+    // Case 1: Invalid bucket names
+    assert_eq!(load(&[("VIDEO_BUCKET", "Videos_Dev")]).unwrap_err(), vec!["VIDEO_BUCKET Videos_Dev is not a valid S3 bucket name"]);
+    assert!(load(&[("VIDEO_BUCKET", "")]).is_err());
+}
@@ -0,0 +1,35 @@
+use cleanup::{execution_outputs, succeeded_files, MapResults};
+
+#[test]
+fn read_map_results() {
+    // Case 0: The Map state output points at the ResultWriter manifest
+    let results: MapResults = serde_json::from_str(
+        r#"{"MapRunArn": "arn:aws:states:us-east-1:123456789012:mapRun:transcribe/S3objectkeys:4f1d", "ResultWriterDetails": {"Bucket": "videos", "Key": "transcriber-results/4f1d/manifest.json"}}"#,
+    )
+    .unwrap();
+    assert_eq!((results.writer.bucket.as_str(), results.writer.key.as_str()), ("videos", "transcriber-results/4f1d/manifest.json"));
+
+    // Case 1: Only the results of child executions that succeeded are read
+    let manifest = br#"{
+        "DestinationBucket": "videos",
+        "MapRunArn": "arn:aws:states:us-east-1:123456789012:mapRun:transcribe/S3objectkeys:4f1d",
+        "ResultFiles": {
+            "FAILED": [{"Key": "transcriber-results/4f1d/FAILED_0.json", "Size": 1120}],
+            "PENDING": [],
+            "SUCCEEDED": [{"Key": "transcriber-results/4f1d/SUCCEEDED_0.json", "Size": 2048}]
+        }
+    }"#;
+    assert_eq!(succeeded_files(manifest).unwrap(), vec!["transcriber-results/4f1d/SUCCEEDED_0.json"]);
+    assert!(succeeded_files(br#"{"ResultFiles": {"FAILED": []}}"#).unwrap().is_empty());
+
+    // Case 2: Each child execution's output is the JSON of one transcriber batch
+    let file = br#"[
+        {"ExecutionArn": "arn:aws:states:us-east-1:123456789012:execution:transcribe/S3objectkeys:1", "Status": "SUCCEEDED", "Output": "{\"items\":[{\"key\":\"week1/video0.mp4\",\"status\":\"uploaded\"}]}"},
+        {"ExecutionArn": "arn:aws:states:us-east-1:123456789012:execution:transcribe/S3objectkeys:2", "Status": "SUCCEEDED", "Output": "{\"items\":[]}"}
+    ]"#;
+    let outputs = execution_outputs(file).unwrap();
+    assert_eq!(outputs.len(), 2);
+    let batch: serde_json::Value = serde_json::from_str(&outputs[0]).unwrap();
+    assert_eq!(batch["items"][0]["key"], "week1/video0.mp4");
+    assert!(execution_outputs(b"{}").is_err());
+}
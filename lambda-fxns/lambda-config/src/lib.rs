@@ -0,0 +1,89 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+// Config loading shared by the lambdas, reporting every missing or invalid value rather than the first
+
+// Every missing or invalid value found while loading the config
+#[derive(Debug)]
+pub struct ConfigError {
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration: {}", self.errors.join("; "))
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// Required S3 bucket name
+pub fn bucket(lookup: &impl Fn(&str) -> Option<String>, name: &str, errors: &mut Vec<String>) -> String {
+    match lookup(name) {
+        Some(bucket) if is_bucket_name(&bucket) => bucket,
+        Some(bucket) => {
+            errors.push(format!("{} {} is not a valid S3 bucket name", name, bucket));
+            String::new()
+        }
+        None => {
+            errors.push(format!("{} not set", name));
+            String::new()
+        }
+    }
+}
+
+// Parse an optional value, falling back to the default when unset or empty
+pub fn parse_or<T: FromStr>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    name: &str,
+    default: T,
+    errors: &mut Vec<String>,
+) -> T
+where
+    T::Err: fmt::Display,
+{
+    match lookup(name).filter(|v| !v.trim().is_empty()) {
+        Some(value) => match value.trim().parse() {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                errors.push(format!("{} {} is invalid: {}", name, value, e));
+                default
+            }
+        },
+        None => default,
+    }
+}
+
+// A parsed value converted to a smaller unit i.e. MB --> bytes, reporting values too large to convert
+pub fn scaled(name: &str, value: u64, factor: u64, errors: &mut Vec<String>) -> u64 {
+    value.checked_mul(factor).unwrap_or_else(|| {
+        errors.push(format!("{} {} is too large", name, value));
+        0
+    })
+}
+
+// Optional path to a file baked into the image
+pub fn file(lookup: &impl Fn(&str) -> Option<String>, name: &str, default: &str, errors: &mut Vec<String>) -> PathBuf {
+    let path = PathBuf::from(lookup(name).filter(|v| !v.is_empty()).unwrap_or_else(|| default.to_string()));
+    if !path.is_file() {
+        errors.push(format!("{} {} does not exist", name, path.display()));
+    }
+    path
+}
+
+// S3 bucket naming rules: 3-63 chars of lowercase letters, digits, '.' and '-',
+// starting and ending with a letter or digit
+pub fn is_bucket_name(name: &str) -> bool {
+    let valid_chars = name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.' || c == '-');
+    let alnum = |c: Option<char>| c.is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+    (3..=63).contains(&name.len())
+        && valid_chars
+        && alnum(name.chars().next())
+        && alnum(name.chars().last())
+        && !name.contains("..")
+        && name.parse::<std::net::Ipv4Addr>().is_err()
+}
@@ -0,0 +1,22 @@
+use std::path::PathBuf;
+
+// Helpers shared by the lambdas' tests, behind the `testing` feature
+
+// Config lookup over fixed vars, later entries override earlier ones i.e. defaults then a test's overrides
+pub fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: Vec<(String, String)> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    move |name| vars.iter().rev().find(|(k, _)| k == name).map(|(_, v)| v.clone())
+}
+
+// Fresh temp dir holding empty files at the given paths i.e. temp_dir("load", &["main", "models/ggml-base.en.bin"])
+pub fn temp_dir(name: &str, files: &[&str]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for file in files {
+        let path = dir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "").unwrap();
+    }
+    dir
+}
@@ -0,0 +1,46 @@
+use lambda_config::{bucket, file, is_bucket_name, parse_or, scaled};
+
+fn lookup(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+    move |name| vars.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string())
+}
+
+#[test]
+fn check_bucket_names() {
+    for name in ["videos", "transcripts-dev", "course.videos.2024", "abc"] {
+        assert!(is_bucket_name(name), "{}", name);
+    }
+    for name in ["ab", "Videos", "videos_dev", "-videos", "videos-", "course..videos", "192.168.0.1", &"a".repeat(64)] {
+        assert!(!is_bucket_name(name), "{}", name);
+    }
+}
+
+#[test]
+fn collect_every_error() {
+    let lookup = lookup(&[("VIDEO_BUCKET", "Videos"), ("MAX_REDRIVES", "ten"), ("CHUNK_SECS", " "), ("WHISPER_MODEL", "")]);
+    let mut errors: Vec<String> = vec![];
+
+    // Case 0: Unset and empty values fall back to the default
+    assert_eq!(parse_or(&lookup, "CHUNK_SECS", 600u64, &mut errors), 600);
+    assert_eq!(parse_or(&lookup, "DEADLINE_RESERVE_SECS", 60u64, &mut errors), 60);
+    assert!(errors.is_empty());
+
+    // Case 1: Invalid values are reported and fall back too, so loading carries on
+    assert_eq!(parse_or(&lookup, "MAX_REDRIVES", 10u32, &mut errors), 10);
+    assert_eq!(bucket(&lookup, "VIDEO_BUCKET", &mut errors), "");
+    assert_eq!(bucket(&lookup, "TRANSCRIPT_BUCKET", &mut errors), "");
+    let model = file(&lookup, "WHISPER_MODEL", "models/ggml-missing.bin", &mut errors);
+    assert_eq!(model.display().to_string(), "models/ggml-missing.bin");
+    // Case 2: Values that overflow once converted are reported rather than panicking
+    assert_eq!(scaled("MAX_VIDEO_MB", 5000, 1024 * 1024, &mut errors), 5_242_880_000);
+    assert_eq!(scaled("MAX_VIDEO_MB", u64::MAX / 1024, 1024 * 1024, &mut errors), 0);
+    assert_eq!(
+        errors,
+        vec![
+            "MAX_REDRIVES ten is invalid: invalid digit found in string",
+            "VIDEO_BUCKET Videos is not a valid S3 bucket name",
+            "TRANSCRIPT_BUCKET not set",
+            "WHISPER_MODEL models/ggml-missing.bin does not exist",
+            "MAX_VIDEO_MB 18014398509481983 is too large",
+        ]
+    );
+}
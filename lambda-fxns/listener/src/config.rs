@@ -0,0 +1,55 @@
+use crate::TriggerMode;
+use lambda_config::parse_or;
+pub use lambda_config::ConfigError;
+use regex::Regex;
+use std::sync::OnceLock;
+
+// Listener configuration, loaded and validated once at cold start
+#[derive(Debug)]
+pub struct Config {
+    pub state_machine_arn: String,
+    pub trigger_mode: TriggerMode,
+    pub pipeline_lock: bool,
+    pub lock_ttl_secs: u64,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Config, ConfigError> {
+        dotenv::dotenv().ok();
+        Config::from_lookup(|name| dotenv::var(name).ok())
+    }
+
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Config, ConfigError> {
+        let mut errors: Vec<String> = vec![];
+        let state_machine_arn = match lookup("STATE_MACHINE_ARN") {
+            Some(arn) if is_state_machine_arn(&arn) => arn,
+            Some(arn) => {
+                errors.push(format!("STATE_MACHINE_ARN {} is not a state machine ARN", arn));
+                String::new()
+            }
+            None => {
+                errors.push("STATE_MACHINE_ARN not set".to_string());
+                String::new()
+            }
+        };
+        let trigger_mode = parse_or(&lookup, "TRIGGER_MODE", TriggerMode::Batch, &mut errors);
+        let pipeline_lock = parse_or(&lookup, "PIPELINE_LOCK", false, &mut errors);
+        let lock_ttl_secs = parse_or(&lookup, "LOCK_TTL_SECS", 6 * 60 * 60, &mut errors);
+        if !errors.is_empty() {
+            return Err(ConfigError { errors });
+        }
+        Ok(Config {
+            state_machine_arn,
+            trigger_mode,
+            pipeline_lock,
+            lock_ttl_secs,
+        })
+    }
+}
+
+// i.e. arn:aws:states:us-east-1:123456789012:stateMachine:transcribe-pipeline
+pub fn is_state_machine_arn(arn: &str) -> bool {
+    static ARN: OnceLock<Regex> = OnceLock::new();
+    ARN.get_or_init(|| Regex::new(r"^arn:aws[a-z-]*:states:[a-z0-9-]+:\d{12}:stateMachine:[A-Za-z0-9_-]{1,80}$").unwrap())
+        .is_match(arn)
+}
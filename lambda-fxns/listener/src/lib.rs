@@ -1,9 +1,365 @@
 use aws_sdk_sfn::{Client, Error};
+use aws_sdk_s3::error::ProvideErrorMetadata;
+use aws_sdk_s3::primitives::ByteStream;
 use aws_config::BehaviorVersion;
+use aws_lambda_events::event::s3::S3EventRecord;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub mod config;
+
+// Marker object uploaded by the CLI once a video directory has been uploaded
+pub const DONE_FILE: &str = "done.txt";
+// Lock object held in the video bucket while a pipeline execution is running
+pub const LOCK_FILE: &str = "pipeline.lock";
+// Optional batch manifest uploaded alongside the done file
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+// How uploads trigger the pipeline
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum TriggerMode {
+    // Wait for a done marker and transcribe every video under its prefix
+    #[default]
+    Batch,
+    // Transcribe each video as soon as it lands
+    Object,
+}
+
+impl FromStr for TriggerMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "batch" => Ok(TriggerMode::Batch),
+            "object" => Ok(TriggerMode::Object),
+            other => Err(format!("Invalid trigger mode {}. Expected 'batch' or 'object'", other)),
+        }
+    }
+}
+
+// Bucket + decoded key of the object referenced by an S3 event record
+#[derive(Debug, PartialEq)]
+pub struct ObjectRef {
+    pub bucket: String,
+    pub key: String,
+    pub etag: Option<String>,
+    pub sequencer: Option<String>,
+    // SQS message the object arrived in, redelivered if its record fails
+    pub message_id: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordStatus {
+    Triggered,
+    Duplicate,
+    Locked,
+    Ignored,
+    Failed,
+}
+
+// Outcome for a single record of an S3 event batch
+#[derive(Serialize, Debug)]
+pub struct RecordOutcome {
+    pub key: Option<String>,
+    pub status: RecordStatus,
+    pub message: String,
+}
+
+// SQS message to redeliver, reported in the batchItemFailures of the response
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItemFailure {
+    pub item_identifier: String,
+}
+
+// SQS messages carrying an object whose record failed, or was turned away by a held lock, so they are retried once the
+// running execution is done. Malformed messages are not listed, as they would only fail again
+pub fn batch_item_failures<'a>(records: impl IntoIterator<Item = (&'a ObjectRef, &'a RecordOutcome)>) -> Vec<BatchItemFailure> {
+    let mut failures: Vec<BatchItemFailure> = vec![];
+    for (object, outcome) in records {
+        if let (Some(id), RecordStatus::Failed | RecordStatus::Locked) = (&object.message_id, &outcome.status) {
+            if !failures.iter().any(|f| &f.item_identifier == id) {
+                failures.push(BatchItemFailure { item_identifier: id.clone() });
+            }
+        }
+    }
+    failures
+}
 
 // Initialize step function client
 pub async fn init_client() -> Result<Client, Error> {
     let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
     let client = Client::new(&config);
     Ok(client)
-}
\ No newline at end of file
+}
+
+// S3 notifications form-encode object keys i.e. "week 1/video0.mp4" arrives as "week+1/video0.mp4"
+pub fn decode_key(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => match (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                (Some(hi), Some(lo)) => {
+                    decoded.push((hi << 4) | lo);
+                    i += 2;
+                }
+                _ => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|d| d as u8)
+}
+
+// Extract the bucket and decoded key from an S3 event record
+pub fn parse_record(record: &S3EventRecord) -> Result<ObjectRef, String> {
+    let bucket = record.s3.bucket.name.as_deref()
+        .filter(|name| !name.is_empty())
+        .ok_or("record is missing s3.bucket.name")?;
+    let key = record.s3.object.key.as_deref()
+        .filter(|key| !key.is_empty())
+        .ok_or("record is missing s3.object.key")?;
+    Ok(ObjectRef {
+        bucket: bucket.to_string(),
+        key: decode_key(key),
+        etag: record.s3.object.e_tag.clone(),
+        sequencer: record.s3.object.sequencer.clone(),
+        message_id: None,
+    })
+}
+
+// EventBridge "Object Created" detail. Keys are delivered as-is (not form-encoded)
+#[derive(Deserialize)]
+struct EventBridgeDetail {
+    bucket: EventBridgeBucket,
+    object: EventBridgeObject,
+}
+
+#[derive(Deserialize)]
+struct EventBridgeBucket {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct EventBridgeObject {
+    key: String,
+    etag: Option<String>,
+    sequencer: Option<String>,
+}
+
+fn parse_eventbridge(event: &Value) -> Result<ObjectRef, String> {
+    let detail: EventBridgeDetail = serde_json::from_value(event["detail"].clone())
+        .map_err(|e| format!("malformed EventBridge detail: {}", e))?;
+    if detail.bucket.name.is_empty() || detail.object.key.is_empty() {
+        return Err("EventBridge detail is missing bucket.name or object.key".to_string());
+    }
+    Ok(ObjectRef {
+        bucket: detail.bucket.name,
+        key: detail.object.key,
+        etag: detail.object.etag,
+        sequencer: detail.object.sequencer,
+        message_id: None,
+    })
+}
+
+// Normalise raw S3 notifications, EventBridge "Object Created" events and SQS-wrapped
+// copies of either into one result per referenced object
+pub fn normalize_event(event: &Value) -> Result<Vec<Result<ObjectRef, String>>, String> {
+    if event["source"] == "aws.s3" {
+        return match event["detail-type"].as_str() {
+            Some("Object Created") => Ok(vec![parse_eventbridge(event)]),
+            Some(other) => Ok(vec![Err(format!("unsupported EventBridge detail-type {}", other))]),
+            None => Err("EventBridge event is missing detail-type".to_string()),
+        };
+    }
+    let records = event["Records"]
+        .as_array()
+        .ok_or("event has neither Records nor an EventBridge detail")?;
+    if records.is_empty() {
+        return Err("event contains no records".to_string());
+    }
+    let mut objects = vec![];
+    for (idx, record) in records.iter().enumerate() {
+        match record["eventSource"].as_str() {
+            Some("aws:s3") => {
+                let parsed = serde_json::from_value::<S3EventRecord>(record.clone())
+                    .map_err(|e| format!("record {}: malformed S3 record: {}", idx, e))
+                    .and_then(|r| parse_record(&r).map_err(|e| format!("record {}: {}", idx, e)));
+                objects.push(parsed);
+            }
+            Some("aws:sqs") => {
+                // SQS bodies carry an S3 notification or EventBridge event as a JSON string
+                let inner = record["body"]
+                    .as_str()
+                    .ok_or_else(|| "missing body".to_string())
+                    .and_then(|body| serde_json::from_str::<Value>(body).map_err(|e| e.to_string()))
+                    .and_then(|body| normalize_event(&body));
+                let message_id = record["messageId"].as_str().map(|id| id.to_string());
+                match inner {
+                    Ok(inner) => objects.extend(inner.into_iter().map(|object| {
+                        object.map(|object| ObjectRef { message_id: message_id.clone(), ..object })
+                    })),
+                    Err(e) => objects.push(Err(format!("record {}: malformed SQS message: {}", idx, e))),
+                }
+            }
+            other => objects.push(Err(format!("record {}: unsupported eventSource {:?}", idx, other))),
+        }
+    }
+    Ok(objects)
+}
+
+// Key prefix scoped by a done marker i.e. "course-a/week03/done.txt" --> "course-a/week03/".
+// None if the key is not a marker
+pub fn marker_prefix(key: &str) -> Option<String> {
+    match key.rsplit_once('/') {
+        Some((prefix, DONE_FILE)) if !prefix.is_empty() => Some(format!("{}/", prefix)),
+        None if key == DONE_FILE => Some(String::new()),
+        _ => None,
+    }
+}
+
+// Video keys follow the upload convention [prefix/]week##/lesson##/video##.mp4
+pub fn is_video_key(key: &str) -> bool {
+    static VIDEO_KEY: OnceLock<Regex> = OnceLock::new();
+    VIDEO_KEY
+        .get_or_init(|| Regex::new(r"^(.+/)?week\d+/lesson\d+/video\d+\.mp4$").unwrap())
+        .is_match(key)
+}
+
+// Deterministic execution name for a marker upload so redelivered notifications map onto the same execution.
+// Names are capped at 80 chars of [A-Za-z0-9-_]
+pub fn execution_name(object: &ObjectRef) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        Some(object.key.as_str()),
+        object.etag.as_deref(),
+        object.sequencer.as_deref(),
+    ] {
+        hasher.update(part.unwrap_or_default().trim_matches('"'));
+        hasher.update(b"\n");
+    }
+    let digest = format!("{:x}", hasher.finalize());
+    let readable: String = object.key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .take(80 - 17)
+        .collect();
+    format!("{}-{}", readable, &digest[..16])
+}
+
+// Step Function execution input describing exactly one batch
+#[derive(Serialize, Debug)]
+pub struct RunInput {
+    pub bucket: String,
+    pub marker_key: String,
+    pub run_id: String,
+    pub prefix: String,
+    pub manifest_key: Option<String>,
+    pub lock_key: Option<String>,
+}
+
+pub enum LockStatus {
+    Acquired,
+    Held { owner: String },
+}
+
+// Outcome of a record that could not take the batch lock. None once the record holds it
+pub fn lock_outcome(object: &ObjectRef, lock: Result<LockStatus, String>) -> Option<RecordOutcome> {
+    let (status, message) = match lock {
+        Ok(LockStatus::Acquired) => return None,
+        Ok(LockStatus::Held { owner }) => (RecordStatus::Locked, format!("LOCKED: Pipeline already running as {}", owner)),
+        Err(e) => (RecordStatus::Failed, format!("ERROR: {}", e)),
+    };
+    Some(RecordOutcome {
+        key: Some(object.key.clone()),
+        status,
+        message,
+    })
+}
+
+// Initialize S3 client
+pub async fn init_s3client() -> aws_sdk_s3::Client {
+    let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+    aws_sdk_s3::Client::new(&config)
+}
+
+// Conditionally create the lock object. A lock older than ttl_secs is treated as stale
+// (i.e. its execution died before cleanup) and replaced
+pub async fn acquire_lock(
+    client: &aws_sdk_s3::Client,
+    bucket: &str,
+    lock_key: &str,
+    owner: &str,
+    ttl_secs: u64,
+) -> Result<LockStatus, String> {
+    for _attempt in 0..2 {
+        let put = client.put_object()
+            .bucket(bucket)
+            .key(lock_key)
+            .if_none_match("*")
+            .metadata("owner", owner)
+            .body(ByteStream::from(owner.as_bytes().to_vec()))
+            .send()
+            .await;
+        let err = match put {
+            Ok(_) => return Ok(LockStatus::Acquired),
+            Err(e) => e,
+        };
+        if !matches!(err.code(), Some("PreconditionFailed") | Some("ConditionalRequestConflict")) {
+            return Err(format!("Failed to create lock {}: {}", lock_key, err));
+        }
+        let head = client.head_object()
+            .bucket(bucket)
+            .key(lock_key)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to read lock {}: {}", lock_key, e))?;
+        let holder = head.metadata()
+            .and_then(|m| m.get("owner"))
+            .cloned()
+            .unwrap_or_default();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+        let age = head.last_modified().map(|t| now - t.secs()).unwrap_or_default();
+        if age < ttl_secs as i64 {
+            return Ok(LockStatus::Held { owner: holder });
+        }
+        tracing::warn!(lock = lock_key, owner = %holder, age, "Replacing stale lock");
+        release_lock(client, bucket, lock_key).await?;
+    }
+    Err(format!("Failed to acquire lock {}", lock_key))
+}
+
+pub async fn release_lock(client: &aws_sdk_s3::Client, bucket: &str, lock_key: &str) -> Result<(), String> {
+    client.delete_object()
+        .bucket(bucket)
+        .key(lock_key)
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Failed to release lock {}: {}", lock_key, e))
+}
+
+// Check whether an object exists i.e. a batch manifest next to the done file
+pub async fn object_exists(client: &aws_sdk_s3::Client, bucket: &str, key: &str) -> Result<bool, String> {
+    match client.head_object().bucket(bucket).key(key).send().await {
+        Ok(_) => Ok(true),
+        Err(e) => match e.into_service_error() {
+            aws_sdk_s3::operation::head_object::HeadObjectError::NotFound(_) => Ok(false),
+            e => Err(format!("Failed to check {}: {}", key, e)),
+        },
+    }
+}
@@ -1,42 +1,174 @@
 use serde::Serialize;
-use aws_lambda_events::event::s3::S3Event;
+use serde_json::Value;
 use lambda_runtime::{run, service_fn, tracing, Error, LambdaEvent};
-use listener::init_client;
+use aws_sdk_sfn::operation::start_execution::StartExecutionError;
+use listener::config::Config;
+use listener::{
+    acquire_lock, batch_item_failures, execution_name, init_client, init_s3client, is_video_key, lock_outcome, marker_prefix,
+    normalize_event, object_exists, release_lock, BatchItemFailure, ObjectRef, RecordOutcome,
+    RecordStatus, RunInput, TriggerMode, LOCK_FILE, MANIFEST_FILE,
+};
 
 #[derive(Serialize)]
 struct Response {
     message: String,
+    records: Vec<RecordOutcome>,
+    // SQS messages to redeliver, read by the event source mapping when ReportBatchItemFailures is on
+    #[serde(rename = "batchItemFailures", skip_serializing_if = "Vec::is_empty")]
+    batch_item_failures: Vec<BatchItemFailure>,
 }
 
-#[tracing::instrument(skip(event), fields(req_id = %event.context.request_id))]
-async fn function_handler(event: LambdaEvent<S3Event>) -> Result<Response, Error> {
-    dotenv::dotenv().ok();
-    let state_machine = dotenv::var("STATE_MACHINE_ARN").expect("STATE_MACHINE_ARN not set");
-    let bucket = event.payload.records[0].s3.bucket.name.as_ref().unwrap().as_str();
-    // Listen for done file
-    let key = event.payload.records[0].s3.object.key.as_ref().unwrap().as_str();
-    let response = match key {
-        "done.txt" => {
-            tracing::info!("Donefile --> {bucket}");
-            // Initialize client
-            let sfn_client = init_client().await.unwrap();
-            // Start execution
-            let payload = r#"{"input": {"payload": "Listener --> Step Function!"}}"#;
-            let _ = sfn_client.start_execution()
-                .state_machine_arn(&state_machine)
-                .input(payload)
-                .send()
-                .await
-                .unwrap();
-            // Trigger step function
-            "Transcription Pipeline triggered".to_string()
+fn outcome(object: &ObjectRef, status: RecordStatus, message: String) -> RecordOutcome {
+    RecordOutcome {
+        key: Some(object.key.clone()),
+        status,
+        message,
+    }
+}
+
+// Start an execution. Existing executions with the same name are treated as success
+async fn start_run(config: &Config, object: &ObjectRef, input: &RunInput) -> Result<RecordOutcome, Error> {
+    let sfn_client = init_client().await?;
+    let payload = serde_json::to_string(input)?;
+    let resp = match sfn_client.start_execution()
+        .state_machine_arn(&config.state_machine_arn)
+        .name(&input.run_id)
+        .input(payload)
+        .send()
+        .await {
+        Ok(_) => outcome(object, RecordStatus::Triggered, format!("Transcription Pipeline triggered: {}", input.run_id)),
+        Err(e) => match e.into_service_error() {
+            // Redelivered notification or re-uploaded marker for an execution that already ran
+            StartExecutionError::ExecutionAlreadyExists(_) => {
+                outcome(object, RecordStatus::Duplicate, format!("DUPLICATE: Execution {} already exists", input.run_id))
+            }
+            e => {
+                tracing::error!(key = %object.key, reason = %e, "Failed to start execution");
+                outcome(object, RecordStatus::Failed, format!("ERROR: Failed to start execution: {}", e))
+            }
+        },
+    };
+    Ok(resp)
+}
+
+// Batch mode: a done marker commits every video under its prefix
+async fn trigger_batch(config: &Config, object: &ObjectRef, prefix: String) -> Result<RecordOutcome, Error> {
+    tracing::info!("Donefile --> {}/{}", object.bucket, prefix);
+    let name = execution_name(object);
+    let s3client = init_s3client().await;
+    let manifest_key = format!("{}{}", prefix, MANIFEST_FILE);
+    let manifest_key = match object_exists(&s3client, &object.bucket, &manifest_key).await {
+        Ok(true) => Some(manifest_key),
+        Ok(false) => None,
+        Err(e) => {
+            tracing::error!(key = %object.key, reason = %e, "Failed to check for manifest");
+            return Ok(outcome(object, RecordStatus::Failed, format!("ERROR: {}", e)));
         }
-        _ => {
-            format!("UPLOAD: {}", &key)
+    };
+    let lock_key = config.pipeline_lock.then(|| format!("{}{}", prefix, LOCK_FILE));
+    // Optionally hold a lock so only one execution per batch runs at a time
+    if let Some(lock_key) = &lock_key {
+        let lock = acquire_lock(&s3client, &object.bucket, lock_key, &name, config.lock_ttl_secs).await;
+        if let Err(e) = &lock {
+            tracing::error!(key = %object.key, reason = %e, "Failed to acquire lock");
+        }
+        if let Some(outcome) = lock_outcome(object, lock) {
+            return Ok(outcome);
         }
+        tracing::info!("Acquired lock {} for {}", lock_key, name);
+    }
+    let input = RunInput {
+        bucket: object.bucket.clone(),
+        marker_key: object.key.clone(),
+        run_id: name,
+        prefix,
+        manifest_key,
+        lock_key: lock_key.clone(),
     };
-    tracing::info!(response);
-    Ok(Response {message: response})
+    let resp = start_run(config, object, &input).await;
+    // Hand the lock back if this record did not start a new execution
+    let started = matches!(&resp, Ok(o) if o.status == RecordStatus::Triggered);
+    if let Some(lock_key) = lock_key.filter(|_| !started) {
+        if let Err(e) = release_lock(&s3client, &object.bucket, &lock_key).await {
+            tracing::error!(reason = %e, "Failed to release lock");
+        }
+    }
+    resp
+}
+
+// Object mode: every video is transcribed as soon as it lands
+async fn trigger_object(config: &Config, object: &ObjectRef) -> Result<RecordOutcome, Error> {
+    tracing::info!("Video --> {}/{}", object.bucket, object.key);
+    // The full key as ItemReader prefix lists exactly this video
+    let input = RunInput {
+        bucket: object.bucket.clone(),
+        marker_key: object.key.clone(),
+        run_id: execution_name(object),
+        prefix: object.key.clone(),
+        manifest_key: None,
+        lock_key: None,
+    };
+    start_run(config, object, &input).await
+}
+
+async fn handle_object(config: &Config, object: &ObjectRef) -> Result<RecordOutcome, Error> {
+    match config.trigger_mode {
+        TriggerMode::Batch => match marker_prefix(&object.key) {
+            // Listen for done files at any prefix i.e. course-a/week03/done.txt
+            Some(prefix) => trigger_batch(config, object, prefix).await,
+            None => Ok(outcome(object, RecordStatus::Ignored, format!("UPLOAD: {}", object.key))),
+        },
+        // Markers and non-media objects are ignored
+        TriggerMode::Object if is_video_key(&object.key) => trigger_object(config, object).await,
+        TriggerMode::Object => Ok(outcome(object, RecordStatus::Ignored, format!("IGNORED: {} is not a video", object.key))),
+    }
+}
+
+#[tracing::instrument(skip(config, event), fields(req_id = %event.context.request_id))]
+async fn function_handler(config: &Config, event: LambdaEvent<Value>) -> Result<Response, Error> {
+    // S3, EventBridge and SQS events all drive the same trigger logic
+    let objects = match normalize_event(&event.payload) {
+        Ok(objects) => objects,
+        Err(e) => {
+            tracing::error!(reason = %e, "Malformed event");
+            return Ok(Response {
+                message: format!("ERROR: Malformed event: {}", e),
+                records: vec![],
+                batch_item_failures: vec![],
+            });
+        }
+    };
+    // Outcome of each record, with the object it referenced where it could be parsed
+    let mut handled: Vec<(Option<ObjectRef>, RecordOutcome)> = vec![];
+    for object in objects {
+        let object = match object {
+            Ok(object) => object,
+            Err(e) => {
+                tracing::error!(reason = %e, "Malformed event record");
+                handled.push((None, RecordOutcome {
+                    key: None,
+                    status: RecordStatus::Failed,
+                    message: format!("ERROR: Malformed {}", e),
+                }));
+                continue;
+            }
+        };
+        let outcome = handle_object(config, &object).await?;
+        tracing::info!("{}", outcome.message);
+        handled.push((Some(object), outcome));
+    }
+    // Failed SQS records are redelivered rather than deleted with the rest of the batch
+    let batch_item_failures = batch_item_failures(handled.iter().filter_map(|(object, outcome)| Some((object.as_ref()?, outcome))));
+    if !batch_item_failures.is_empty() {
+        tracing::error!(messages = batch_item_failures.len(), "Returning failed SQS messages for redelivery");
+    }
+    let outcomes: Vec<RecordOutcome> = handled.into_iter().map(|(_, outcome)| outcome).collect();
+    let triggered = outcomes.iter().filter(|o| o.status == RecordStatus::Triggered).count();
+    Ok(Response {
+        message: format!("Processed {} records, {} triggered", outcomes.len(), triggered),
+        records: outcomes,
+        batch_item_failures,
+    })
 }
 
 #[tokio::main]
@@ -48,5 +180,14 @@ async fn main() -> Result<(), Error> {
         .with_target(false)
         .init();
 
-    run(service_fn(function_handler)).await
+    // Load config once per cold start
+    let config = match Config::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!(errors = ?e.errors, "Invalid configuration");
+            return Err(e.into());
+        }
+    };
+    let config = &config;
+    run(service_fn(move |event| async move { function_handler(config, event).await })).await
 }
@@ -0,0 +1,50 @@
+use listener::config::Config;
+use listener::TriggerMode;
+use lambda_config::testing::lookup;
+
+fn load(vars: &[(&str, &str)]) -> Result<Config, Vec<String>> {
+    Config::from_lookup(lookup(vars)).map_err(|e| e.errors)
+}
+
+const ARN: &str = "arn:aws:states:us-east-1:123456789012:stateMachine:transcribe-pipeline";
+
+#[test]
+fn load_listener_config() {
+    // Case 0: Defaults
+    let config = load(&[("STATE_MACHINE_ARN", ARN)]).unwrap();
+    assert_eq!(config.trigger_mode, TriggerMode::Batch);
+    assert!(!config.pipeline_lock);
+    assert_eq!(config.lock_ttl_secs, 21600);
+
+    // Case 1: Overrides, empty values fall back to defaults
+    let config = load(&[
+        ("STATE_MACHINE_ARN", ARN),
+        ("TRIGGER_MODE", "object"),
+        ("PIPELINE_LOCK", "true"),
+        ("LOCK_TTL_SECS", ""),
+    ])
+    .unwrap();
+    assert_eq!(config.trigger_mode, TriggerMode::Object);
+    assert!(config.pipeline_lock);
+    assert_eq!(config.lock_ttl_secs, 21600);
+}
+
+#[test]
+fn report_every_config_error() {
+    // Case 0: Missing ARN
+    assert_eq!(load(&[]).unwrap_err(), vec!["STATE_MACHINE_ARN not set"]);
+
+    // Case 1: All invalid values are reported together
+    let errors = load(&[
+        ("STATE_MACHINE_ARN", "arn:aws:lambda:us-east-1:123456789012:function:listener"),
+        ("TRIGGER_MODE", "stream"),
+        ("PIPELINE_LOCK", "yes"),
+        ("LOCK_TTL_SECS", "-1"),
+    ])
+    .unwrap_err();
+    assert_eq!(errors.len(), 4);
+    assert!(errors[0].starts_with("STATE_MACHINE_ARN"));
+    assert!(errors[1].starts_with("TRIGGER_MODE"));
+    assert!(errors[2].starts_with("PIPELINE_LOCK"));
+    assert!(errors[3].starts_with("LOCK_TTL_SECS"));
+}
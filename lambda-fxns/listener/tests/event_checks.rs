@@ -0,0 +1,69 @@
+use listener::{normalize_event, ObjectRef};
+use serde_json::{json, Value};
+
+fn fixture(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap()
+}
+
+fn marker() -> ObjectRef {
+    ObjectRef {
+        bucket: "videos".to_string(),
+        key: "course a/week03/done.txt".to_string(),
+        etag: Some("d41d8cd98f00b204e9800998ecf8427e".to_string()),
+        sequencer: Some("0066ABE3F7D1A0C3B4".to_string()),
+        message_id: None,
+    }
+}
+
+#[test]
+fn normalize_s3_notification() {
+    let event = fixture(include_str!("fixtures/s3_put.json"));
+    let objects = normalize_event(&event).unwrap();
+    assert_eq!(objects.len(), 2);
+    assert_eq!(objects[0].as_ref().unwrap().key, "week1/lesson1/video0.mp4");
+    // Form-encoded keys are decoded
+    assert_eq!(objects[1], Ok(marker()));
+}
+
+#[test]
+fn normalize_eventbridge_event() {
+    let event = fixture(include_str!("fixtures/eventbridge_object_created.json"));
+    let objects = normalize_event(&event).unwrap();
+    assert_eq!(objects, vec![Ok(marker())]);
+}
+
+#[test]
+fn normalize_sqs_wrapped_events() {
+    // SQS messages wrapping an S3 notification and an EventBridge event
+    let event = fixture(include_str!("fixtures/sqs_wrapped.json"));
+    let objects = normalize_event(&event).unwrap();
+    let from = |id: &str| ObjectRef { message_id: Some(id.to_string()), ..marker() };
+    assert_eq!(
+        objects,
+        vec![Ok(from("059f36b4-87a3-44ab-83d2-661975830a7d")), Ok(from("2e1424d4-f796-459a-8184-9c92662be6da"))]
+    );
+}
+
+#[test]
+fn reject_malformed_events() {
+    // Case 0: Unknown shapes and empty batches fail the whole event
+    assert!(normalize_event(&json!({})).is_err());
+    assert!(normalize_event(&json!({"Records": []})).is_err());
+
+    // Case 1: Malformed records fail individually
+    let event = json!({
+        "Records": [
+            {"eventSource": "aws:sqs", "body": "not json"},
+            {"eventSource": "aws:dynamodb"}
+        ]
+    });
+    let objects = normalize_event(&event).unwrap();
+    assert_eq!(objects.len(), 2);
+    assert!(objects.iter().all(|o| o.is_err()));
+
+    // Case 2: Other EventBridge S3 events are not object creations
+    let mut event = fixture(include_str!("fixtures/eventbridge_object_created.json"));
+    event["detail-type"] = json!("Object Deleted");
+    let objects = normalize_event(&event).unwrap();
+    assert!(objects[0].is_err());
+}
@@ -0,0 +1,56 @@
+use listener::{decode_key, is_video_key, marker_prefix, TriggerMode};
+
+#[test]
+fn decode_event_keys() {
+    // Case 0: Plain keys pass through
+    assert_eq!(decode_key("done.txt"), "done.txt");
+    assert_eq!(decode_key("week1/lesson1/video0.mp4"), "week1/lesson1/video0.mp4");
+
+    // Case 1: Spaces are form-encoded as '+'
+    assert_eq!(decode_key("course+a/done.txt"), "course a/done.txt");
+
+    // Case 2: Percent-encoded bytes incl. multi-byte utf-8
+    assert_eq!(decode_key("week%2B1/done.txt"), "week+1/done.txt");
+    assert_eq!(decode_key("caf%C3%A9/done.txt"), "café/done.txt");
+
+    // Case 3: Malformed escapes are kept verbatim
+    assert_eq!(decode_key("100%/done.txt"), "100%/done.txt");
+    assert_eq!(decode_key("week%zz/done%2"), "week%zz/done%2");
+}
+
+#[test]
+fn scope_marker_prefixes() {
+    // Case 0: Root and nested markers
+    assert_eq!(marker_prefix("done.txt"), Some("".to_string()));
+    assert_eq!(marker_prefix("course-a/done.txt"), Some("course-a/".to_string()));
+    assert_eq!(marker_prefix("course-a/week03/done.txt"), Some("course-a/week03/".to_string()));
+
+    // Case 1: Non-marker keys
+    assert_eq!(marker_prefix("week1/lesson1/video0.mp4"), None);
+    assert_eq!(marker_prefix("course-a/not-done.txt"), None);
+    assert_eq!(marker_prefix("done.txt/video0.mp4"), None);
+    assert_eq!(marker_prefix("/done.txt"), None);
+}
+
+#[test]
+fn match_video_keys() {
+    // Case 0: Videos with and without a course prefix
+    assert!(is_video_key("week1/lesson1/video0.mp4"));
+    assert!(is_video_key("course-a/week01/lesson02/video03.mp4"));
+
+    // Case 1: Markers, manifests and stray files
+    assert!(!is_video_key("done.txt"));
+    assert!(!is_video_key("course-a/week1/done.txt"));
+    assert!(!is_video_key("course-a/manifest.json"));
+    assert!(!is_video_key("week1/lesson1/video0.mov"));
+    assert!(!is_video_key("week1/lesson1/notes.mp4"));
+    assert!(!is_video_key("lesson1/video0.mp4"));
+}
+
+#[test]
+fn parse_trigger_modes() {
+    assert_eq!("batch".parse::<TriggerMode>(), Ok(TriggerMode::Batch));
+    assert_eq!("Object".parse::<TriggerMode>(), Ok(TriggerMode::Object));
+    assert!("stream".parse::<TriggerMode>().is_err());
+    assert_eq!(TriggerMode::default(), TriggerMode::Batch);
+}
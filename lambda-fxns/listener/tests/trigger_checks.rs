@@ -0,0 +1,80 @@
+use listener::{batch_item_failures, execution_name, lock_outcome, LockStatus, ObjectRef, RecordOutcome, RecordStatus};
+
+fn marker(key: &str, etag: &str, sequencer: &str) -> ObjectRef {
+    ObjectRef {
+        bucket: "videos".to_string(),
+        key: key.to_string(),
+        etag: Some(etag.to_string()),
+        sequencer: Some(sequencer.to_string()),
+        message_id: None,
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    name.len() <= 80 && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[test]
+fn name_executions_per_record() {
+    let name = execution_name(&marker("course-a/week03/done.txt", "d41d8cd98f00b204e9800998ecf8427e", "0066ABE3F7D1A0C3B4"));
+    assert!(name.starts_with("course-a-week03-done-txt-"));
+    assert!(is_valid_name(&name));
+
+    // Case 0: Redelivered notifications map onto the same execution, quoted or not
+    let redelivered = marker("course-a/week03/done.txt", "\"d41d8cd98f00b204e9800998ecf8427e\"", "0066ABE3F7D1A0C3B4");
+    assert_eq!(execution_name(&redelivered), name);
+
+    // Case 1: A re-uploaded marker is a new event
+    let reuploaded = marker("course-a/week03/done.txt", "d41d8cd98f00b204e9800998ecf8427e", "0066ABE3F7D1A0C3C9");
+    assert_ne!(execution_name(&reuploaded), name);
+    let other = marker("course-a/week04/done.txt", "d41d8cd98f00b204e9800998ecf8427e", "0066ABE3F7D1A0C3B4");
+    assert_ne!(execution_name(&other), name);
+
+    // Case 2: Long keys and characters outside [A-Za-z0-9-_] still give a valid name
+    let long = marker(&format!("{}/café week/done.txt", "course".repeat(20)), "d41d8cd98f00b204e9800998ecf8427e", "0066ABE3F7D1A0C3B4");
+    let name = execution_name(&long);
+    assert_eq!(name.len(), 80);
+    assert!(is_valid_name(&name));
+    let longer = marker(&format!("{}/other/done.txt", "course".repeat(20)), "d41d8cd98f00b204e9800998ecf8427e", "0066ABE3F7D1A0C3B4");
+    assert_ne!(execution_name(&longer), name);
+}
+
+#[test]
+fn map_lock_status() {
+    let object = marker("course-a/week03/done.txt", "d41d8cd98f00b204e9800998ecf8427e", "0066ABE3F7D1A0C3B4");
+
+    // Case 0: The record holds the lock and goes on to start its execution
+    assert!(lock_outcome(&object, Ok(LockStatus::Acquired)).is_none());
+
+    // Case 1: Another execution holds it
+    let held = lock_outcome(&object, Ok(LockStatus::Held { owner: "course-a-week03-done-txt-4f1d".to_string() })).unwrap();
+    assert_eq!(held.status, RecordStatus::Locked);
+    assert_eq!(held.key.as_deref(), Some("course-a/week03/done.txt"));
+    assert!(held.message.ends_with("course-a-week03-done-txt-4f1d"));
+
+    // Case 2: S3 errors fail the record
+    let failed = lock_outcome(&object, Err("Failed to read lock".to_string())).unwrap();
+    assert_eq!(failed.status, RecordStatus::Failed);
+}
+
+#[test]
+fn redeliver_failed_messages() {
+    let object = |key: &str, message_id: Option<&str>| ObjectRef {
+        message_id: message_id.map(|id| id.to_string()),
+        ..marker(key, "d41d8cd98f00b204e9800998ecf8427e", "0066ABE3F7D1A0C3B4")
+    };
+    let outcome = |status: RecordStatus| RecordOutcome { key: None, status, message: String::new() };
+    // Case 0: Failed and locked records are retried, once per message
+    let records = [
+        (object("course-a/week01/done.txt", Some("059f36b4")), outcome(RecordStatus::Triggered)),
+        (object("course-a/week02/done.txt", Some("2e1424d4")), outcome(RecordStatus::Failed)),
+        (object("course-a/week03/done.txt", Some("2e1424d4")), outcome(RecordStatus::Failed)),
+        (object("course-a/week04/done.txt", Some("7d3e11a9")), outcome(RecordStatus::Locked)),
+        // Case 1: Objects delivered straight from S3 or EventBridge have no message to redeliver
+        (object("course-a/week05/done.txt", None), outcome(RecordStatus::Failed)),
+    ];
+    let failures = batch_item_failures(records.iter().map(|(o, r)| (o, r)));
+    let ids: Vec<&str> = failures.iter().map(|f| f.item_identifier.as_str()).collect();
+    assert_eq!(ids, vec!["2e1424d4", "7d3e11a9"]);
+    assert_eq!(serde_json::to_value(&failures[0]).unwrap(), serde_json::json!({"itemIdentifier": "2e1424d4"}));
+}
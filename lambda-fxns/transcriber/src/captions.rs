@@ -0,0 +1,221 @@
+use crate::transcript::{Segment, Transcript};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+// Transcript formats uploaded for each video
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Json,
+    Txt,
+    Srt,
+    Vtt,
+}
+
+impl OutputFormat {
+    pub fn ext(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Txt => "txt",
+            OutputFormat::Srt => "srt",
+            OutputFormat::Vtt => "vtt",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "application/json",
+            OutputFormat::Txt => "text/plain; charset=utf-8",
+            OutputFormat::Srt => "application/x-subrip; charset=utf-8",
+            OutputFormat::Vtt => "text/vtt; charset=utf-8",
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.ext())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(OutputFormat::Json),
+            "txt" => Ok(OutputFormat::Txt),
+            "srt" => Ok(OutputFormat::Srt),
+            "vtt" => Ok(OutputFormat::Vtt),
+            other => Err(format!("unknown output format {} (expected json, txt, srt or vtt)", other)),
+        }
+    }
+}
+
+// Comma separated list of formats i.e. "txt,srt,vtt". Duplicates are dropped, order is kept
+pub fn parse_formats(value: &str) -> Result<Vec<OutputFormat>, String> {
+    let mut formats: Vec<OutputFormat> = vec![];
+    for part in value.split(',').filter(|p| !p.trim().is_empty()) {
+        let format: OutputFormat = part.parse()?;
+        if !formats.contains(&format) {
+            formats.push(format);
+        }
+    }
+    if formats.is_empty() {
+        return Err("no output formats given".to_string());
+    }
+    Ok(formats)
+}
+
+// Caption segmentation limits. Defaults follow common broadcast guidelines
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptionLimits {
+    pub max_line_len: usize,
+    pub max_lines: usize,
+    pub max_cps: f64,
+}
+
+impl Default for CaptionLimits {
+    fn default() -> Self {
+        CaptionLimits {
+            max_line_len: 42,
+            max_lines: 2,
+            max_cps: 17.0,
+        }
+    }
+}
+
+// A single caption as shown on screen
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub lines: Vec<String>,
+}
+
+impl Cue {
+    // Characters read by the viewer, counting the break between lines as a space
+    pub fn chars(&self) -> usize {
+        let text: usize = self.lines.iter().map(|l| l.chars().count()).sum();
+        text + self.lines.len().saturating_sub(1)
+    }
+}
+
+// Greedily wrap words into lines of at most max_line_len chars. Words longer than a line are kept whole
+fn wrap(text: &str, max_line_len: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let len = line.chars().count();
+        if len > 0 && len + 1 + word.chars().count() > max_line_len {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+// Shortest time a cue must stay on screen to be read at max_cps
+fn min_duration_ms(cue: &Cue, max_cps: f64) -> u64 {
+    (cue.chars() as f64 * 1000.0 / max_cps).ceil() as u64
+}
+
+// Split whisper segments into cues that respect the line length, line count and reading speed limits
+pub fn cues(segments: &[Segment], limits: &CaptionLimits) -> Vec<Cue> {
+    let max_line_len = limits.max_line_len.max(1);
+    let max_lines = limits.max_lines.max(1);
+    let mut cues: Vec<Cue> = vec![];
+    for segment in segments {
+        let lines = wrap(&segment.text, max_line_len);
+        let chunks: Vec<Vec<String>> = lines.chunks(max_lines).map(|c| c.to_vec()).collect();
+        // Share the segment's time between its cues by character count
+        let total: usize = chunks.iter().map(|c| c.iter().map(|l| l.chars().count() + 1).sum::<usize>()).sum();
+        let duration = segment.end_ms.saturating_sub(segment.start_ms);
+        let mut offset = 0;
+        for lines in chunks {
+            let chars: usize = lines.iter().map(|l| l.chars().count() + 1).sum();
+            let start_ms = segment.start_ms + duration * offset as u64 / total as u64;
+            offset += chars;
+            let end_ms = segment.start_ms + duration * offset as u64 / total as u64;
+            cues.push(Cue { start_ms, end_ms, lines });
+        }
+    }
+    // Keep fast cues on screen longer, first using the silence before the next cue, then any
+    // time the next cue has to spare. Cue order and end times are never moved backwards
+    if limits.max_cps > 0.0 {
+        for i in 0..cues.len() {
+            let need = min_duration_ms(&cues[i], limits.max_cps);
+            let want_end = cues[i].start_ms + need;
+            if cues[i].end_ms >= want_end {
+                continue;
+            }
+            let limit = match cues.get(i + 1) {
+                Some(next) => {
+                    let spare = next.end_ms.saturating_sub(next.start_ms).saturating_sub(min_duration_ms(next, limits.max_cps));
+                    next.start_ms + spare
+                }
+                None => want_end,
+            };
+            cues[i].end_ms = want_end.min(limit).max(cues[i].end_ms);
+            let end_ms = cues[i].end_ms;
+            if let Some(next) = cues.get_mut(i + 1) {
+                next.start_ms = next.start_ms.max(end_ms);
+            }
+        }
+    }
+    cues
+}
+
+// hh:mm:ss{sep}mmm
+fn timestamp(ms: u64, sep: char) -> String {
+    format!("{:02}:{:02}:{:02}{}{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, sep, ms % 1000)
+}
+
+pub fn render_srt(cues: &[Cue]) -> String {
+    let mut out = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        out.push_str(&format!("{}\n{} --> {}\n", i + 1, timestamp(cue.start_ms, ','), timestamp(cue.end_ms, ',')));
+        for line in &cue.lines {
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out
+}
+
+// WebVTT cue text is markup, so escape anything a browser would parse as a tag or entity
+fn vtt_escape(line: &str) -> String {
+    line.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+pub fn render_vtt(cues: &[Cue]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for cue in cues {
+        out.push_str(&format!("{} --> {}\n", timestamp(cue.start_ms, '.'), timestamp(cue.end_ms, '.')));
+        for line in &cue.lines {
+            out.push_str(&vtt_escape(line));
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out
+}
+
+// Render the transcript in the requested format
+pub fn render(format: OutputFormat, transcript: &Transcript, limits: &CaptionLimits) -> Result<String, String> {
+    let rendered = match format {
+        OutputFormat::Json => serde_json::to_string_pretty(transcript).map_err(|e| e.to_string())?,
+        OutputFormat::Txt => transcript.text(),
+        OutputFormat::Srt => render_srt(&cues(&transcript.segments, limits)),
+        OutputFormat::Vtt => render_vtt(&cues(&transcript.segments, limits)),
+    };
+    Ok(rendered)
+}
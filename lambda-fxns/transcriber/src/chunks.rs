@@ -0,0 +1,191 @@
+use crate::transcript::{Segment, Transcript, TranscriptParams};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+// Bumped whenever the chunk map layout changes. Maps of another version are ignored
+pub const CHUNK_MAP_VERSION: u32 = 1;
+
+// Words compared either side of a chunk boundary when removing repeated speech
+const ALIGN_WORDS: usize = 16;
+
+// How long audio is split. Audio up to chunk_ms long is transcribed in one go
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Chunking {
+    // Length of each chunk, 0 disables chunking
+    pub chunk_ms: u64,
+    // Audio shared by consecutive chunks so no word is cut in half at a boundary
+    pub overlap_ms: u64,
+}
+
+impl Default for Chunking {
+    fn default() -> Self {
+        Chunking {
+            chunk_ms: 20 * 60 * 1000,
+            overlap_ms: 30 * 1000,
+        }
+    }
+}
+
+impl Chunking {
+    pub fn enabled_for(&self, duration_ms: u64) -> bool {
+        self.chunk_ms > 0 && duration_ms > self.chunk_ms
+    }
+}
+
+// Progress of a chunked transcription, persisted after every chunk so a later invocation
+// can resume from the last completed one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChunkMap {
+    pub version: u32,
+    pub source_key: String,
+    pub model: String,
+    pub params: TranscriptParams,
+    pub duration_ms: u64,
+    pub chunking: Chunking,
+    pub chunks: Vec<Chunk>,
+}
+
+// Slice of the source audio and its transcript, on the source timeline, once done
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chunk {
+    pub index: usize,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub transcript: Option<Transcript>,
+}
+
+impl ChunkMap {
+    pub fn new(source_key: &str, model: &str, params: TranscriptParams, duration_ms: u64, chunking: Chunking) -> ChunkMap {
+        ChunkMap {
+            version: CHUNK_MAP_VERSION,
+            source_key: source_key.to_string(),
+            model: model.to_string(),
+            params,
+            duration_ms,
+            chunking,
+            chunks: plan_chunks(duration_ms, &chunking),
+        }
+    }
+
+    // A saved map can only be resumed when it was made from the same audio with the same settings
+    pub fn resumes(&self, fresh: &ChunkMap) -> bool {
+        self.version == fresh.version
+            && self.source_key == fresh.source_key
+            && self.model == fresh.model
+            && self.params.settings() == fresh.params.settings()
+            && self.duration_ms == fresh.duration_ms
+            && self.chunking == fresh.chunking
+            && self.chunks.len() == fresh.chunks.len()
+    }
+
+    pub fn completed(&self) -> usize {
+        self.chunks.iter().filter(|c| c.transcript.is_some()).count()
+    }
+}
+
+// Where chunk maps are kept between invocations, keyed by video key
+pub trait ChunkStore {
+    fn load(&self, key: &str) -> impl Future<Output = Result<Option<ChunkMap>, String>> + Send;
+    fn save(&self, key: &str, map: &ChunkMap) -> impl Future<Output = Result<(), String>> + Send;
+    fn remove(&self, key: &str) -> impl Future<Output = Result<(), String>> + Send;
+}
+
+// Overlapping chunks covering the whole audio i.e. 50 min with 20 min chunks and 30s overlap
+// --> [0, 20:00], [19:30, 39:30], [39:00, 50:00]
+pub fn plan_chunks(duration_ms: u64, chunking: &Chunking) -> Vec<Chunk> {
+    if !chunking.enabled_for(duration_ms) {
+        return vec![Chunk { index: 0, start_ms: 0, end_ms: duration_ms, transcript: None }];
+    }
+    let step = chunking.chunk_ms.saturating_sub(chunking.overlap_ms).max(1);
+    let mut chunks: Vec<Chunk> = vec![];
+    let mut start_ms = 0;
+    loop {
+        let end_ms = (start_ms + chunking.chunk_ms).min(duration_ms);
+        chunks.push(Chunk { index: chunks.len(), start_ms, end_ms, transcript: None });
+        if end_ms >= duration_ms {
+            break;
+        }
+        start_ms += step;
+    }
+    chunks
+}
+
+// Move a chunk's transcript from the chunk's timeline onto the source timeline
+pub fn shift(transcript: &mut Transcript, offset_ms: u64) {
+    for segment in &mut transcript.segments {
+        segment.start_ms += offset_ms;
+        segment.end_ms += offset_ms;
+        for word in &mut segment.words {
+            word.start_ms += offset_ms;
+            word.end_ms += offset_ms;
+            for token in &mut word.tokens {
+                token.start_ms += offset_ms;
+                token.end_ms += offset_ms;
+            }
+        }
+    }
+}
+
+// Lowercase letters and digits only, so punctuation and casing differences still align
+pub(crate) fn normalize(word: &str) -> String {
+    word.chars().filter(|c| c.is_alphanumeric()).flat_map(|c| c.to_lowercase()).collect()
+}
+
+// Longest run of words (at least 2) ending the tail that also starts the head
+fn repeated_words(tail: &[String], head: &[String]) -> usize {
+    let max = tail.len().min(head.len());
+    (2..=max).rev().find(|&k| tail[tail.len() - k..] == head[..k]).unwrap_or(0)
+}
+
+// Drop the first n words from a run of segments, keeping word timings in step with the text
+fn drop_leading_words(segments: &mut Vec<Segment>, mut n: usize) {
+    while n > 0 && !segments.is_empty() {
+        let segment = &mut segments[0];
+        let text: Vec<String> = segment.text.split_whitespace().map(|w| w.to_string()).collect();
+        if n >= text.len() {
+            n -= text.len();
+            segments.remove(0);
+            continue;
+        }
+        segment.text = text[n..].join(" ");
+        if segment.words.len() == text.len() {
+            segment.words.drain(..n);
+            if let Some(word) = segment.words.first() {
+                segment.start_ms = word.start_ms;
+            }
+        }
+        n = 0;
+    }
+}
+
+// Join chunk transcripts into one. Segments are split between chunks at the middle of their
+// overlap, then any words repeated across the cut are removed from the later chunk
+pub fn stitch(map: &ChunkMap) -> Option<Transcript> {
+    let mut chunks = map.chunks.iter();
+    let first = chunks.next()?;
+    let mut stitched = first.transcript.clone()?;
+    let mut prev_end = first.end_ms;
+    for chunk in chunks {
+        let transcript = chunk.transcript.as_ref()?;
+        let cut = (chunk.start_ms + prev_end) / 2;
+        let mid = |s: &Segment| (s.start_ms + s.end_ms) / 2;
+        stitched.segments.retain(|s| mid(s) < cut);
+        let mut next: Vec<Segment> = transcript.segments.iter().filter(|s| mid(s) >= cut).cloned().collect();
+        let tail: Vec<String> = stitched.segments.iter().flat_map(|s| s.text.split_whitespace().map(normalize)).collect();
+        let tail = &tail[tail.len().saturating_sub(ALIGN_WORDS)..];
+        let head: Vec<String> = next.iter().flat_map(|s| s.text.split_whitespace().map(normalize)).take(ALIGN_WORDS).collect();
+        drop_leading_words(&mut next, repeated_words(tail, &head));
+        // Keep the timeline continuous across the cut
+        let last_end = stitched.segments.last().map(|s| s.end_ms).unwrap_or(0);
+        if let Some(segment) = next.first_mut() {
+            segment.start_ms = segment.start_ms.max(last_end);
+            segment.end_ms = segment.end_ms.max(segment.start_ms);
+        }
+        if stitched.language.is_empty() {
+            stitched.language = transcript.language.clone();
+        }
+        stitched.segments.extend(next);
+        prev_end = chunk.end_ms;
+    }
+    Some(stitched)
+}
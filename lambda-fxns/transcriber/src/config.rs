@@ -0,0 +1,198 @@
+use crate::captions::{parse_formats, CaptionLimits, OutputFormat};
+use crate::chunks::Chunking;
+use crate::cpu::{select_build, CpuFeatures, CpuLevel};
+use crate::filter::{parse_extensions, ItemFilter};
+use crate::options::{is_language, DecodeParams};
+use crate::parallelism::Resources;
+use crate::quality::QualityLimits;
+use crate::vad::Vad;
+use lambda_config::{bucket, file, parse_or, scaled};
+pub use lambda_config::ConfigError;
+use regex::Regex;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Transcriber configuration, loaded and validated once at cold start
+#[derive(Debug)]
+pub struct Config {
+    pub video_bucket: String,
+    pub transcript_bucket: String,
+    // Build of WHISPER_BIN for this CPU i.e. ./main-avx2
+    pub whisper_bin: PathBuf,
+    // Instruction set of that build, None when WHISPER_BIN is used as it is
+    pub whisper_build: Option<CpuLevel>,
+    // Features of the CPU the function landed on
+    pub cpu: CpuFeatures,
+    // Default model, other models are looked up next to it
+    pub whisper_model: PathBuf,
+    // Default spoken language, or auto to detect it
+    pub whisper_language: String,
+    // Default decoding parameters, overridable per run
+    pub decode: DecodeParams,
+    // Default transcript formats, overridable per run
+    pub output_formats: Vec<OutputFormat>,
+    pub caption_limits: CaptionLimits,
+    // Long audio is transcribed in overlapping chunks, with progress saved after each one
+    pub chunking: Chunking,
+    // Which listed objects are transcribed
+    pub item_filter: ItemFilter,
+    // vCPUs and memory shared by the items of a batch
+    pub resources: Resources,
+    // Most items transcribed at once, 0 to go by the resources alone
+    pub max_parallel_items: usize,
+    // Pipe videos from S3 into ffmpeg instead of downloading them to /tmp first
+    pub stream_videos: bool,
+    // Hallucinated artifacts, loops and speech rate checked in every transcript
+    pub quality: QualityLimits,
+    // Voice activity detection, so only speech is sent to whisper
+    pub vad: Option<Vad>,
+    // Time kept back from the invocation deadline to upload finished transcripts and respond
+    pub deadline_reserve: Duration,
+    // Times the state machine may re-drive deferred items before they are reported as failed
+    pub max_redrives: u32,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Config, ConfigError> {
+        dotenv::dotenv().ok();
+        Config::from_lookup(|name| dotenv::var(name).ok())
+    }
+
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Config, ConfigError> {
+        let mut errors: Vec<String> = vec![];
+        let video_bucket = bucket(&lookup, "VIDEO_BUCKET", &mut errors);
+        let transcript_bucket = bucket(&lookup, "TRANSCRIPT_BUCKET", &mut errors);
+        // Lambda hardware varies, so the whisper build is picked for the CPU at cold start
+        let cpu = CpuFeatures::detect();
+        let bin = PathBuf::from(lookup("WHISPER_BIN").filter(|v| !v.is_empty()).unwrap_or_else(|| "./main".to_string()));
+        let (whisper_bin, whisper_build) = match select_build(&bin, cpu.level(), |path| path.is_file()) {
+            Some(build) => (build.bin, build.level),
+            None => {
+                errors.push(format!("WHISPER_BIN {} does not exist, nor does a {}-<cpu> build of it", bin.display(), bin.display()));
+                (bin, None)
+            }
+        };
+        let whisper_model = file(&lookup, "WHISPER_MODEL", "models/ggml-base.en.bin", &mut errors);
+        let whisper_language = lookup("WHISPER_LANGUAGE").filter(|v| !v.trim().is_empty()).unwrap_or_else(|| "en".to_string());
+        if !is_language(&whisper_language) {
+            errors.push(format!("WHISPER_LANGUAGE {} is not a language code or auto", whisper_language));
+        }
+        let extensions = parse_extensions(&lookup("MEDIA_EXTENSIONS").unwrap_or_else(|| "mp4".to_string()));
+        if extensions.is_empty() {
+            errors.push("MEDIA_EXTENSIONS must list at least one extension".to_string());
+        }
+        let skip_keys = lookup("SKIP_KEYS").filter(|v| !v.trim().is_empty()).and_then(|value| {
+            Regex::new(&value)
+                .map_err(|e| errors.push(format!("SKIP_KEYS {} is not a valid pattern: {}", value, e)))
+                .ok()
+        });
+        let max_video_mb: u64 = parse_or(&lookup, "MAX_VIDEO_MB", 5000, &mut errors);
+        let item_filter = ItemFilter {
+            extensions,
+            skip_keys,
+            max_bytes: scaled("MAX_VIDEO_MB", max_video_mb, 1024 * 1024, &mut errors),
+        };
+        // Lambda sets the memory size, and vCPUs scale with it
+        let resources = Resources {
+            vcpus: std::thread::available_parallelism().map(|n| n.get() as u32).unwrap_or(4),
+            memory_mb: parse_or(&lookup, "AWS_LAMBDA_FUNCTION_MEMORY_SIZE", 2048, &mut errors),
+        };
+        let max_parallel_items = parse_or(&lookup, "MAX_PARALLEL_ITEMS", 0, &mut errors);
+        // Decoder threads default to every core the function has, shared out when items run in parallel
+        let decode_defaults = DecodeParams {
+            threads: resources.vcpus,
+            ..DecodeParams::default()
+        };
+        let decode = DecodeParams {
+            beam_size: parse_or(&lookup, "WHISPER_BEAM_SIZE", decode_defaults.beam_size, &mut errors),
+            best_of: parse_or(&lookup, "WHISPER_BEST_OF", decode_defaults.best_of, &mut errors),
+            temperature: parse_or(&lookup, "WHISPER_TEMPERATURE", decode_defaults.temperature, &mut errors),
+            temperature_inc: parse_or(&lookup, "WHISPER_TEMPERATURE_INC", decode_defaults.temperature_inc, &mut errors),
+            threads: parse_or(&lookup, "WHISPER_THREADS", decode_defaults.threads, &mut errors),
+            max_len: parse_or(&lookup, "WHISPER_MAX_LEN", decode_defaults.max_len, &mut errors),
+            suppress_non_speech: parse_or(&lookup, "WHISPER_SUPPRESS_NON_SPEECH", decode_defaults.suppress_non_speech, &mut errors),
+            entropy_thold: parse_or(&lookup, "WHISPER_ENTROPY_THOLD", decode_defaults.entropy_thold, &mut errors),
+            logprob_thold: parse_or(&lookup, "WHISPER_LOGPROB_THOLD", decode_defaults.logprob_thold, &mut errors),
+        };
+        errors.extend(decode.validate().into_iter().map(|e| format!("Invalid WHISPER_* default: {}", e)));
+        let output_formats = match lookup("OUTPUT_FORMATS").filter(|v| !v.trim().is_empty()) {
+            Some(value) => parse_formats(&value).unwrap_or_else(|e| {
+                errors.push(format!("OUTPUT_FORMATS {} is invalid: {}", value, e));
+                vec![]
+            }),
+            None => vec![OutputFormat::Txt],
+        };
+        let defaults = CaptionLimits::default();
+        let caption_limits = CaptionLimits {
+            max_line_len: parse_or(&lookup, "CAPTION_MAX_LINE_LEN", defaults.max_line_len, &mut errors),
+            max_lines: parse_or(&lookup, "CAPTION_MAX_LINES", defaults.max_lines, &mut errors),
+            max_cps: parse_or(&lookup, "CAPTION_MAX_CPS", defaults.max_cps, &mut errors),
+        };
+        if caption_limits.max_line_len == 0 || caption_limits.max_lines == 0 {
+            errors.push("CAPTION_MAX_LINE_LEN and CAPTION_MAX_LINES must be at least 1".to_string());
+        }
+        if !(caption_limits.max_cps > 0.0 && caption_limits.max_cps.is_finite()) {
+            errors.push(format!("CAPTION_MAX_CPS {} must be a positive number", caption_limits.max_cps));
+        }
+        let defaults = Chunking::default();
+        let chunk_secs: u64 = parse_or(&lookup, "CHUNK_SECS", defaults.chunk_ms / 1000, &mut errors);
+        let overlap_secs: u64 = parse_or(&lookup, "CHUNK_OVERLAP_SECS", defaults.overlap_ms / 1000, &mut errors);
+        if chunk_secs > 0 && overlap_secs >= chunk_secs {
+            errors.push(format!("CHUNK_OVERLAP_SECS {} must be less than CHUNK_SECS {}", overlap_secs, chunk_secs));
+        }
+        let chunking = Chunking {
+            chunk_ms: scaled("CHUNK_SECS", chunk_secs, 1000, &mut errors),
+            overlap_ms: scaled("CHUNK_OVERLAP_SECS", overlap_secs, 1000, &mut errors),
+        };
+        let stream_videos = parse_or(&lookup, "STREAM_VIDEOS", false, &mut errors);
+        let defaults = QualityLimits::default();
+        let quality = QualityLimits {
+            clean: parse_or(&lookup, "QUALITY_CLEAN", defaults.clean, &mut errors),
+            max_words_per_sec: parse_or(&lookup, "QUALITY_MAX_WPS", defaults.max_words_per_sec, &mut errors),
+            min_repeats: parse_or(&lookup, "QUALITY_MIN_REPEATS", defaults.min_repeats, &mut errors),
+        };
+        if !(quality.max_words_per_sec > 0.0 && quality.max_words_per_sec.is_finite()) {
+            errors.push(format!("QUALITY_MAX_WPS {} must be a positive number", quality.max_words_per_sec));
+        }
+        if quality.min_repeats < 2 {
+            errors.push(format!("QUALITY_MIN_REPEATS {} must be at least 2", quality.min_repeats));
+        }
+        let defaults = Vad::default();
+        let vad = Vad {
+            threshold_db: parse_or(&lookup, "VAD_THRESHOLD_DB", defaults.threshold_db, &mut errors),
+            min_speech_ms: parse_or(&lookup, "VAD_MIN_SPEECH_MS", defaults.min_speech_ms, &mut errors),
+            min_silence_ms: parse_or(&lookup, "VAD_MIN_SILENCE_MS", defaults.min_silence_ms, &mut errors),
+            pad_ms: parse_or(&lookup, "VAD_PAD_MS", defaults.pad_ms, &mut errors),
+        };
+        if !(vad.threshold_db < 0.0 && vad.threshold_db.is_finite()) {
+            errors.push(format!("VAD_THRESHOLD_DB {} must be a negative dBFS level", vad.threshold_db));
+        }
+        let vad = parse_or(&lookup, "VAD", false, &mut errors).then_some(vad);
+        let deadline_reserve = Duration::from_secs(parse_or(&lookup, "DEADLINE_RESERVE_SECS", 60, &mut errors));
+        let max_redrives = parse_or(&lookup, "MAX_REDRIVES", 10, &mut errors);
+        if !errors.is_empty() {
+            return Err(ConfigError { errors });
+        }
+        Ok(Config {
+            video_bucket,
+            transcript_bucket,
+            whisper_bin,
+            whisper_build,
+            cpu,
+            whisper_model,
+            whisper_language,
+            decode,
+            output_formats,
+            caption_limits,
+            chunking,
+            item_filter,
+            resources,
+            max_parallel_items,
+            stream_videos,
+            quality,
+            vad,
+            deadline_reserve,
+            max_redrives,
+        })
+    }
+}
@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+
+// Instruction sets whisper.cpp is built for, least to most capable. Each build runs on any CPU at its level or above
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CpuLevel {
+    Generic,
+    Avx,
+    Avx2,
+    Avx512,
+}
+
+impl CpuLevel {
+    // Suffix of the build in the image i.e. ./main-avx2
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            CpuLevel::Generic => "generic",
+            CpuLevel::Avx => "avx",
+            CpuLevel::Avx2 => "avx2",
+            CpuLevel::Avx512 => "avx512",
+        }
+    }
+}
+
+const LEVELS: [CpuLevel; 4] = [CpuLevel::Avx512, CpuLevel::Avx2, CpuLevel::Avx, CpuLevel::Generic];
+
+// x86 features the whisper.cpp builds are compiled with
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CpuFeatures {
+    pub avx: bool,
+    pub avx2: bool,
+    pub fma: bool,
+    pub f16c: bool,
+    pub avx512f: bool,
+    pub avx512bw: bool,
+    pub avx512vl: bool,
+    pub avx512dq: bool,
+    pub avx512cd: bool,
+}
+
+impl CpuFeatures {
+    #[cfg(target_arch = "x86_64")]
+    pub fn detect() -> CpuFeatures {
+        CpuFeatures {
+            avx: std::arch::is_x86_feature_detected!("avx"),
+            avx2: std::arch::is_x86_feature_detected!("avx2"),
+            fma: std::arch::is_x86_feature_detected!("fma"),
+            f16c: std::arch::is_x86_feature_detected!("f16c"),
+            avx512f: std::arch::is_x86_feature_detected!("avx512f"),
+            avx512bw: std::arch::is_x86_feature_detected!("avx512bw"),
+            avx512vl: std::arch::is_x86_feature_detected!("avx512vl"),
+            avx512dq: std::arch::is_x86_feature_detected!("avx512dq"),
+            avx512cd: std::arch::is_x86_feature_detected!("avx512cd"),
+        }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    pub fn detect() -> CpuFeatures {
+        CpuFeatures::default()
+    }
+
+    // Most capable build this CPU runs. The AVX2 and AVX512 builds also use FMA and F16C, and the AVX512 build every
+    // AVX512 subset the compiler enables with it (F, BW, VL, DQ and CD)
+    pub fn level(&self) -> CpuLevel {
+        let avx2 = self.avx && self.avx2 && self.fma && self.f16c;
+        let avx512 = self.avx512f && self.avx512bw && self.avx512vl && self.avx512dq && self.avx512cd;
+        if avx2 && avx512 {
+            CpuLevel::Avx512
+        } else if avx2 {
+            CpuLevel::Avx2
+        } else if self.avx {
+            CpuLevel::Avx
+        } else {
+            CpuLevel::Generic
+        }
+    }
+}
+
+// whisper.cpp build picked for this CPU, None when WHISPER_BIN itself is used
+#[derive(Debug, Clone, PartialEq)]
+pub struct WhisperBuild {
+    pub bin: PathBuf,
+    pub level: Option<CpuLevel>,
+}
+
+// The most capable build of bin the CPU runs i.e. ./main --> ./main-avx2, falling back level by level to
+// ./main-generic and then to bin itself. None when not one of them exists
+pub fn select_build(bin: &Path, level: CpuLevel, exists: impl Fn(&Path) -> bool) -> Option<WhisperBuild> {
+    let mut candidates: Vec<WhisperBuild> = LEVELS
+        .iter()
+        .filter(|l| **l <= level)
+        .map(|l| {
+            let mut path = bin.as_os_str().to_owned();
+            path.push(format!("-{}", l.suffix()));
+            WhisperBuild { bin: PathBuf::from(path), level: Some(*l) }
+        })
+        .collect();
+    candidates.push(WhisperBuild { bin: bin.to_path_buf(), level: None });
+    candidates.into_iter().find(|build| exists(&build.bin))
+}
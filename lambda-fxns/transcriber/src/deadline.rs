@@ -0,0 +1,32 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// When work has to stop: the invocation's deadline, less the time kept back to upload
+// finished transcripts and respond before Lambda kills the process
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    at: Instant,
+}
+
+impl Deadline {
+    // From the Lambda context deadline, in milliseconds since the epoch
+    pub fn from_epoch_ms(deadline_ms: u64, reserve: Duration) -> Deadline {
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+        Deadline::after(Duration::from_millis(deadline_ms.saturating_sub(now_ms)), reserve)
+    }
+
+    pub fn after(timeout: Duration, reserve: Duration) -> Deadline {
+        Deadline {
+            at: Instant::now() + timeout.saturating_sub(reserve),
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+
+    // Whether work expected to take as long as the estimate can still finish in time
+    pub fn allows(&self, estimate: Duration) -> bool {
+        let remaining = self.remaining();
+        !remaining.is_zero() && estimate < remaining
+    }
+}
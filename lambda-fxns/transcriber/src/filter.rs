@@ -0,0 +1,69 @@
+use regex::Regex;
+
+// Content types S3 records when the uploader did not say what the object is
+const GENERIC_CONTENT_TYPES: [&str; 3] = ["application/octet-stream", "binary/octet-stream", "application/mp4"];
+
+// Which listed objects are videos to transcribe. The Map state lists the whole prefix, so done
+// markers, manifests, vocabularies and stray files arrive alongside the videos
+#[derive(Debug, Clone)]
+pub struct ItemFilter {
+    // Lowercase file extensions without the dot i.e. ["mp4"]
+    pub extensions: Vec<String>,
+    // Keys never transcribed i.e. ^archive/
+    pub skip_keys: Option<Regex>,
+    // Largest object accepted, 0 for no limit
+    pub max_bytes: u64,
+}
+
+// Comma separated extensions i.e. "mp4, .MOV" --> ["mp4", "mov"]
+pub fn parse_extensions(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+        .filter(|ext| !ext.is_empty())
+        .collect()
+}
+
+impl ItemFilter {
+    // Reason to skip an object going by its listing alone, before any request is made for it
+    pub fn check_listing(&self, key: &str, size: u64) -> Result<(), String> {
+        if key.ends_with('/') {
+            return Err("folder marker".to_string());
+        }
+        let ext = match key.rsplit('/').next().and_then(|file| file.rsplit_once('.')) {
+            Some((stem, ext)) if !stem.is_empty() => ext.to_lowercase(),
+            _ => String::new(),
+        };
+        if !self.extensions.contains(&ext) {
+            return Err(match ext.as_str() {
+                "" => "no file extension".to_string(),
+                ext => format!(".{} is not a media extension ({})", ext, self.extensions.join(", ")),
+            });
+        }
+        if let Some(re) = &self.skip_keys {
+            if re.is_match(key) {
+                return Err(format!("key matches SKIP_KEYS {}", re.as_str()));
+            }
+        }
+        if size == 0 {
+            return Err("empty object".to_string());
+        }
+        if self.max_bytes > 0 && size > self.max_bytes {
+            return Err(format!("{} bytes is over the {} byte limit", size, self.max_bytes));
+        }
+        Ok(())
+    }
+
+    // Reason to skip an object going by the Content-Type recorded when it was uploaded.
+    // A missing or generic type leaves it to the extension
+    pub fn check_content_type(&self, content_type: Option<&str>) -> Result<(), String> {
+        let essence = match content_type.map(|t| t.split(';').next().unwrap_or("").trim().to_lowercase()) {
+            Some(essence) if !essence.is_empty() => essence,
+            _ => return Ok(()),
+        };
+        if essence.starts_with("video/") || essence.starts_with("audio/") || GENERIC_CONTENT_TYPES.contains(&essence.as_str()) {
+            return Ok(());
+        }
+        Err(format!("content type {} is not audio or video", essence))
+    }
+}
@@ -0,0 +1,58 @@
+use std::path::{Component, Path, PathBuf};
+
+// Mapping between S3 keys and scratch paths. Nested layouts round-trip exactly i.e.
+// week1/lesson1/video0.mp4 --> {videos}/week1/lesson1/video0.mp4
+//                          --> {transcripts}/week1/lesson1/video0.txt --> week1/lesson1/video0.txt
+
+// Relative path for a key. Keys that could escape the scratch dir are rejected
+fn relative_path(key: &str) -> Result<PathBuf, String> {
+    let path = Path::new(key);
+    let valid = !key.is_empty()
+        && !key.ends_with('/')
+        && path.components().all(|c| matches!(c, Component::Normal(_)))
+        && key.split('/').all(|part| !part.is_empty());
+    if !valid {
+        return Err(format!("Invalid object key {}", key));
+    }
+    Ok(path.to_path_buf())
+}
+
+// Scratch path a video key is downloaded to
+pub fn video_path(video_dir: &Path, key: &str) -> Result<PathBuf, String> {
+    Ok(video_dir.join(relative_path(key)?))
+}
+
+// Transcript key for a video key i.e. week1/lesson1/video0.mp4 --> week1/lesson1/video0.txt
+pub fn transcript_key(key: &str, ext: &str) -> String {
+    let (dir, file) = match key.rsplit_once('/') {
+        Some((dir, file)) => (Some(dir), file),
+        None => (None, key),
+    };
+    let stem = match file.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => file,
+    };
+    match dir {
+        Some(dir) => format!("{}/{}.{}", dir, stem, ext),
+        None => format!("{}.{}", stem, ext),
+    }
+}
+
+// Scratch path a video key's transcript is written to
+pub fn transcript_path(tscript_dir: &Path, key: &str, ext: &str) -> Result<PathBuf, String> {
+    relative_path(key)?;
+    Ok(tscript_dir.join(transcript_key(key, ext)))
+}
+
+// S3 key for a file under a scratch dir, the inverse of video_path/transcript_path
+pub fn path_key(dir: &Path, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(dir).ok()?;
+    let parts: Option<Vec<&str>> = rel
+        .components()
+        .map(|c| match c {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect();
+    Some(parts?.join("/")).filter(|key| !key.is_empty())
+}
@@ -1,73 +1,254 @@
-use aws_sdk_s3::{Client, Error};
-use aws_config::BehaviorVersion;
-use aws_sdk_s3::primitives::ByteStream;
-use tokio::fs::{File, create_dir_all};
-use tokio::io::copy;
-use std::path::Path;
-
-
-pub struct PutResponse {
-    pub key: String,
-    pub status: i32,
-    pub message: String
-}
-
-// Create S3 client
-pub async fn init_s3client() -> Result<Client, Error> {
-    let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
-    let client = Client::new(&config);
-    Ok(client)
-}
-
-pub async fn get_video(client: &Client, bucket: &str, key: &str) -> Result<(), Error> {
-    // Get response
-    let resp = client.get_object().bucket(bucket).key(key).send().await?;
-    // Get video as byte stream from response body
-    let mut stream = resp.body.into_async_read();
-    // Create a file to write the video data to
-    let tmp_path = format!("/tmp/videos/{}", key);
-    let dir_path = Path::new(&tmp_path).parent().expect("No parent directory found");
-    create_dir_all(&dir_path).await.expect("Failed to create directories");
-    let mut tmp_file = File::create(&tmp_path).await.unwrap();
-    // Write the video data into the file
-    let _file_msg = copy(&mut stream, &mut tmp_file).await.unwrap();
-    Ok(())
-}
-
-pub async fn put_transcript(client: &Client, bucket: &str, filepath: &Path) -> Result<PutResponse, Error> {
-    let stream = ByteStream::from_path(filepath).await;
-    let path_str = filepath.display().to_string();
-    let key = path_str.strip_prefix("/tmp/transcripts/").unwrap();
-    match stream {
-        Ok(body) => {
-            match client.put_object()
-                .bucket(bucket)
-                .key(key)
-                .body(body)
-                .send()
-                .await {
-                    Ok(_) => {
-                        Ok(PutResponse {
-                            key: key.to_string(),
-                            status: 200,
-                            message: format!("SUCCESS: S3 upload {}", key)
-                        })
-                    }
-                    Err(e) => {
-                        Ok(PutResponse {
-                            key: key.to_string(),
-                            status: 400,
-                            message: format!("ERROR: Failed S3 upload {} : {}", key, e)
-                        })
-                }
-            }
-        }
-        Err(e) => {
-            Ok(PutResponse {
-                key: key.to_string(),
-                status: 400,
-                message: format!("ERROR: Failed to extract bytestream from {}, {}", key, e)
-            })
-        }
-    }
+use aws_sdk_s3::{Client, Error};
+use aws_config::BehaviorVersion;
+use aws_sdk_s3::primitives::ByteStream;
+use serde::Deserialize;
+use options::RunOptions;
+use vocabulary::{Vocabulary, VocabularyStore};
+use aws_sdk_s3::operation::get_object::GetObjectError;
+use aws_sdk_s3::operation::head_object::HeadObjectError;
+use aws_sdk_s3::presigning::PresigningConfig;
+use chunks::{ChunkMap, ChunkStore, Chunking};
+use media::{mp4_layout, Layout, VideoInput, HEADER_BYTES};
+use pipeline::probe_duration;
+use stamp::Stamp;
+use tokio::fs::{File, create_dir_all};
+use tokio::io::copy;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub mod captions;
+pub mod chunks;
+pub mod config;
+pub mod cpu;
+pub mod deadline;
+pub mod filter;
+pub mod keys;
+pub mod media;
+pub mod options;
+pub mod parallelism;
+pub mod pipeline;
+pub mod quality;
+pub mod report;
+pub mod scratch;
+pub mod stamp;
+pub mod transcript;
+pub mod vad;
+pub mod vocabulary;
+
+
+// Presigned video URLs outlive any invocation
+const PRESIGN_EXPIRY: Duration = Duration::from_secs(60 * 60);
+
+pub struct PutResponse {
+    pub key: String,
+    pub status: i32,
+    pub message: String
+}
+
+// Optional batch manifest uploaded alongside the done file
+#[derive(Deserialize, Default)]
+pub struct Manifest {
+    // Video keys that make up the batch. All listed items are processed when absent
+    pub videos: Option<Vec<String>>,
+    // Transcribe the videos again even where their transcripts are current, for runs started by done.txt
+    #[serde(default)]
+    pub force: bool,
+    // Formats, model and language for this batch. The execution input takes precedence
+    #[serde(flatten)]
+    pub options: RunOptions,
+}
+
+impl Manifest {
+    // Whether the manifest lists a video, by its full key or relative to the run prefix i.e. with prefix course-a/week1/,
+    // "lesson1/video0.mp4" and "course-a/week1/lesson1/video0.mp4" both list course-a/week1/lesson1/video0.mp4
+    pub fn lists(&self, prefix: &str, key: &str) -> bool {
+        match &self.videos {
+            Some(videos) => videos.iter().any(|video| video == key || key.strip_prefix(prefix) == Some(video.as_str())),
+            None => true,
+        }
+    }
+}
+
+// Create S3 client
+pub async fn init_s3client() -> Result<Client, Error> {
+    let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+    let client = Client::new(&config);
+    Ok(client)
+}
+
+// Download a video into the invocation's scratch dir i.e. {video_dir}/week##/lesson##/video##.mp4
+pub async fn get_video(client: &Client, bucket: &str, key: &str, video_dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+    let tmp_path = keys::video_path(video_dir, key)?;
+    // Get response
+    let resp = client.get_object().bucket(bucket).key(key).send().await?;
+    // Get video as byte stream from response body
+    let mut stream = resp.body.into_async_read();
+    // Create a file to write the video data to
+    if let Some(dir_path) = tmp_path.parent() {
+        create_dir_all(dir_path).await?;
+    }
+    let mut tmp_file = File::create(&tmp_path).await?;
+    // Write the video data into the file
+    let _file_msg = copy(&mut stream, &mut tmp_file).await?;
+    Ok(tmp_path)
+}
+
+// Open a video to stream straight into ffmpeg. Videos that cannot be decoded front to back, or are long
+// enough to be chunked, are read through a presigned URL instead so ffmpeg can seek with range requests
+pub async fn open_video(client: &Client, bucket: &str, key: &str, chunking: &Chunking) -> Result<VideoInput, Box<dyn std::error::Error + Send + Sync>> {
+    let resp = client.get_object().bucket(bucket).key(key).range(format!("bytes=0-{}", HEADER_BYTES - 1)).send().await?;
+    let header = resp.body.collect().await?.into_bytes();
+    let presigned = client.get_object().bucket(bucket).key(key).presigned(PresigningConfig::expires_in(PRESIGN_EXPIRY)?).await?;
+    let url = VideoInput::Url(presigned.uri().to_string());
+    let layout = mp4_layout(&header);
+    if layout != Layout::Sequential {
+        tracing::info!("{}: {:?} layout, reading with range requests", key, layout);
+        return Ok(url);
+    }
+    if chunking.chunk_ms > 0 {
+        match probe_duration(&url).await {
+            Ok(duration_ms) if chunking.enabled_for(duration_ms) => {
+                tracing::info!("{}: {}s long, reading chunks with range requests", key, duration_ms / 1000);
+                return Ok(url);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("ERROR: Failed to probe {}: {}", key, e),
+        }
+    }
+    let resp = client.get_object().bucket(bucket).key(key).send().await?;
+    Ok(VideoInput::Stream(Box::pin(resp.body.into_async_read())))
+}
+
+// Content-Type recorded for an object, if any
+pub async fn get_content_type(client: &Client, bucket: &str, key: &str) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let resp = client.head_object().bucket(bucket).key(key).send().await?;
+    Ok(resp.content_type().map(|t| t.to_string()))
+}
+
+// Stamp recorded on an uploaded transcript, None when there is no such object or it has none
+pub async fn get_stamp(client: &Client, bucket: &str, key: &str) -> Result<Option<Stamp>, Box<dyn std::error::Error + Send + Sync>> {
+    let resp = match client.head_object().bucket(bucket).key(key).send().await {
+        Ok(resp) => resp,
+        Err(e) => match e.into_service_error() {
+            HeadObjectError::NotFound(_) => return Ok(None),
+            e => return Err(e.into()),
+        },
+    };
+    Ok(resp.metadata().and_then(Stamp::from_metadata))
+}
+
+pub async fn get_manifest(client: &Client, bucket: &str, key: &str) -> Result<Manifest, Box<dyn std::error::Error + Send + Sync>> {
+    let resp = client.get_object().bucket(bucket).key(key).send().await?;
+    let body = resp.body.collect().await?.into_bytes();
+    let manifest: Manifest = serde_json::from_slice(&body)?;
+    Ok(manifest)
+}
+
+// Course vocabulary, None when there is no such object
+pub async fn get_vocabulary(client: &Client, bucket: &str, key: &str) -> Result<Option<Vocabulary>, Box<dyn std::error::Error + Send + Sync>> {
+    let resp = match client.get_object().bucket(bucket).key(key).send().await {
+        Ok(resp) => resp,
+        Err(e) => match e.into_service_error() {
+            GetObjectError::NoSuchKey(_) => return Ok(None),
+            e => return Err(e.into()),
+        },
+    };
+    let body = resp.body.collect().await?.into_bytes();
+    let vocabulary: Vocabulary = serde_json::from_slice(&body)?;
+    Ok(Some(vocabulary))
+}
+
+// Vocabularies read from the video bucket
+pub struct S3VocabularyStore<'a> {
+    pub client: &'a Client,
+    pub bucket: &'a str,
+}
+
+impl VocabularyStore for S3VocabularyStore<'_> {
+    async fn load(&self, key: &str) -> Result<Option<Vocabulary>, String> {
+        get_vocabulary(self.client, self.bucket, key).await.map_err(|e| e.to_string())
+    }
+}
+
+// Chunk maps kept in the transcript bucket next to the transcripts i.e. week1/lesson1/video0.chunks.json
+pub struct S3ChunkStore<'a> {
+    pub client: &'a Client,
+    pub bucket: &'a str,
+}
+
+impl ChunkStore for S3ChunkStore<'_> {
+    async fn load(&self, key: &str) -> Result<Option<ChunkMap>, String> {
+        let map_key = keys::transcript_key(key, "chunks.json");
+        let resp = match self.client.get_object().bucket(self.bucket).key(&map_key).send().await {
+            Ok(resp) => resp,
+            Err(e) => match e.into_service_error() {
+                GetObjectError::NoSuchKey(_) => return Ok(None),
+                e => return Err(e.to_string()),
+            },
+        };
+        let body = resp.body.collect().await.map_err(|e| e.to_string())?.into_bytes();
+        // A map from an older layout is started over rather than failing the video
+        Ok(serde_json::from_slice(&body).ok())
+    }
+
+    async fn save(&self, key: &str, map: &ChunkMap) -> Result<(), String> {
+        let body = serde_json::to_vec(map).map_err(|e| e.to_string())?;
+        self.client.put_object()
+            .bucket(self.bucket)
+            .key(keys::transcript_key(key, "chunks.json"))
+            .content_type("application/json")
+            .body(ByteStream::from(body))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), String> {
+        self.client.delete_object()
+            .bucket(self.bucket)
+            .key(keys::transcript_key(key, "chunks.json"))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+pub async fn put_transcript(client: &Client, bucket: &str, filepath: &Path, key: &str, content_type: &str, stamp: &Stamp) -> Result<PutResponse, Error> {
+    let stream = ByteStream::from_path(filepath).await;
+    match stream {
+        Ok(body) => {
+            match client.put_object()
+                .bucket(bucket)
+                .key(key)
+                .content_type(content_type)
+                .set_metadata(Some(stamp.metadata()))
+                .body(body)
+                .send()
+                .await {
+                    Ok(_) => {
+                        Ok(PutResponse {
+                            key: key.to_string(),
+                            status: 200,
+                            message: format!("SUCCESS: S3 upload {}", key)
+                        })
+                    }
+                    Err(e) => {
+                        Ok(PutResponse {
+                            key: key.to_string(),
+                            status: 400,
+                            message: format!("ERROR: Failed S3 upload {} : {}", key, e)
+                        })
+                }
+            }
+        }
+        Err(e) => {
+            Ok(PutResponse {
+                key: key.to_string(),
+                status: 400,
+                message: format!("ERROR: Failed to extract bytestream from {}, {}", key, e)
+            })
+        }
+    }
 }
\ No newline at end of file
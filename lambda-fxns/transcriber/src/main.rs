@@ -1,10 +1,25 @@
-use glob::glob;
 use lambda_runtime::{run, service_fn, tracing, Error, LambdaEvent};
 use serde::{Deserialize, Serialize};
-use transcriber::{init_s3client, get_video, put_transcript};
-use std::process::Command;
+use aws_sdk_s3::Client;
+use futures::stream::{self, StreamExt};
+use transcriber::config::Config;
+use transcriber::deadline::Deadline;
+use transcriber::filter::ItemFilter;
+use transcriber::keys::transcript_key;
+use transcriber::media::VideoInput;
+use transcriber::options::{requested_formats, resolve, DecodeParams, RunOptions};
+use transcriber::parallelism::{item_memory_mb, longest_decode_ms, plan};
+use transcriber::pipeline::{transcribe_key, ChunkRun, Outputs, Timings, TranscribeError, Whisper};
+use transcriber::report::{BatchFailure, ItemOutcome, ItemStatus, Stage};
+use transcriber::scratch::{Scratch, SCRATCH_ROOT};
+use transcriber::stamp::Stamp;
+use transcriber::vocabulary::{Corrections, Vocabularies};
+use transcriber::{init_s3client, get_content_type, get_manifest, get_stamp, get_video, get_vocabulary, open_video, put_transcript, Manifest, S3ChunkStore, S3VocabularyStore};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
 struct ItemDetails {
     etag: String,
@@ -14,10 +29,44 @@ struct ItemDetails {
     storage_class: String,
 }
 
+// Execution input forwarded by the Map state's ItemBatcher
+#[derive(Deserialize)]
+struct RunContext {
+    bucket: String,
+    run_id: String,
+    // Listed by the Map state, and what manifest entries are relative to
+    #[serde(default)]
+    prefix: String,
+    manifest_key: Option<String>,
+    // Transcribe every video again, even where its transcripts are current
+    #[serde(default)]
+    force: bool,
+    // Formats, model and language for this run, overriding the manifest and config
+    #[serde(flatten)]
+    options: RunOptions,
+}
+
+#[derive(Deserialize)]
+struct BatchInput {
+    run: RunContext,
+}
+
+// Results of earlier passes over the batch, passed back in when the state machine re-drives deferred items
+#[derive(Deserialize, Default)]
+struct PreviousResults {
+    processed: Vec<String>,
+    failed: Vec<String>,
+    #[serde(default)]
+    items: Vec<ItemOutcome>,
+    redrives: u32,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "PascalCase")]
 struct S3Items {
     items: Vec<ItemDetails>,
+    batch_input: Option<BatchInput>,
+    previous: Option<PreviousResults>,
 }
 
 #[derive(Serialize)]
@@ -25,88 +74,422 @@ struct TranscriberResponse {
     message: String,
     processed: Vec<String>,
     failed: Vec<String>,
+    // What happened to each listed object, including markers and stray files that were skipped
+    items: Vec<ItemOutcome>,
+    // Items not finished before the deadline, re-driven by the state machine
+    deferred: Vec<ItemDetails>,
+    redrives: u32,
 }
 
+// Shared by every item of the batch
+struct Batch<'a> {
+    s3client: &'a Client,
+    video_bucket: &'a str,
+    tscript_bucket: &'a str,
+    item_filter: &'a ItemFilter,
+    // Stream videos into ffmpeg rather than download them to video_dir
+    stream_videos: bool,
+    // Transcribe videos whose transcripts are current
+    force: bool,
+    video_dir: PathBuf,
+    tscript_dir: PathBuf,
+    // Without the vocabulary, set per item
+    whisper: Whisper,
+    outputs: Outputs,
+    vocabularies: Vocabularies,
+    chunks: ChunkRun<'a, S3ChunkStore<'a>>,
+}
 
-async fn function_handler(event: LambdaEvent<S3Items>) -> Result<TranscriberResponse, Error> {
-    // Init S3 client
-    let s3client = init_s3client().await.unwrap();
-    // Env Vars
-    dotenv::dotenv().ok();
-    let video_bucket = dotenv::var("VIDEO_BUCKET").expect("VIDEO_BUCKET not set");
-    let tscript_bucket = dotenv::var("TRANSCRIPT_BUCKET").expect("TRANSCRIPT_BUCKET not set");
-    // Process event payload
-    let items = event.payload.items;
-    // Download videos to /tmp/videos/
-    for item in items {
-        tracing::info!("Processing: {}", item.key);
-        match get_video(&s3client, &video_bucket, &item.key).await {
-            Ok(_) => {
-                tracing::info!("SUCCESS: Downloaded {}", item.key);
-            },
-            Err(e) => {
-                tracing::error!("ERROR: Failed to download {}: {}", item.key, e);
+// Milliseconds since started
+fn elapsed_ms(started: Instant) -> Option<u64> {
+    Some(started.elapsed().as_millis() as u64)
+}
+
+// Whether every requested format is in S3 with the same stamp
+async fn transcripts_current(batch: &Batch<'_>, key: &str, stamp: &Stamp) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    for format in &batch.outputs.formats {
+        let existing = get_stamp(batch.s3client, batch.tscript_bucket, &transcript_key(key, format.ext())).await?;
+        if existing.as_ref() != Some(stamp) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// Download, transcribe and upload one video, side by side with up to parallelism.items others, each whisper run on
+// its share of the threads. Its transcripts are in S3 as soon as it finishes, whatever the deadline does to the rest
+async fn process_item(batch: &Batch<'_>, item: &ItemDetails) -> ItemOutcome {
+    let key = item.key.as_str();
+    tracing::info!("Processing: {}", key);
+    let mut outcome = ItemOutcome::new(key);
+    // The video's own vocabulary goes into its prompt, corrections and stamp
+    let vocabulary = batch.vocabularies.get(key);
+    if let Some((vocabulary_key, _)) = vocabulary {
+        tracing::info!("Vocabulary for {}: {}", key, vocabulary_key);
+    }
+    let whisper = Whisper {
+        prompt: vocabulary.and_then(|(_, v)| v.prompt()),
+        vocabulary_key: vocabulary.map(|(k, _)| k.to_string()),
+        ..batch.whisper.clone()
+    };
+    let outputs = Outputs {
+        corrections: vocabulary.map(|(_, v)| v.corrections()).unwrap_or_default(),
+        ..batch.outputs.clone()
+    };
+    // Re-runs after a partial failure leave alone the videos that were transcribed before it
+    let stamp = Stamp::new(&item.etag, &whisper.model_name(), &whisper.params());
+    if !batch.force {
+        match transcripts_current(batch, key, &stamp).await {
+            Ok(true) => {
+                tracing::info!("Skipping {}: transcripts are current", key);
+                outcome.status = ItemStatus::Current;
+                outcome.outputs = batch.outputs.formats.iter().map(|f| transcript_key(key, f.ext())).collect();
+                return outcome;
+            }
+            Ok(false) => {}
+            Err(e) => tracing::error!("ERROR: Failed to check transcripts of {}: {}", key, e),
+        }
+    }
+    // Objects uploaded as something other than audio or video are left alone
+    match get_content_type(batch.s3client, batch.video_bucket, key).await {
+        Ok(content_type) => {
+            if let Err(reason) = batch.item_filter.check_content_type(content_type.as_deref()) {
+                tracing::info!("Skipping {}: {}", key, reason);
+                return ItemOutcome::skipped(key, reason);
+            }
+        }
+        Err(e) => tracing::error!("ERROR: Failed to read content type of {}: {}", key, e),
+    }
+    let started = Instant::now();
+    let video = match batch.stream_videos {
+        true => open_video(batch.s3client, batch.video_bucket, key, &batch.chunks.chunking).await,
+        false => get_video(batch.s3client, batch.video_bucket, key, &batch.video_dir).await.map(VideoInput::File),
+    };
+    outcome.durations.download_ms = elapsed_ms(started);
+    let video = match video {
+        Ok(video) => {
+            match &video {
+                VideoInput::File(path) => tracing::info!("SUCCESS: Downloaded {} to {}", key, path.display()),
+                _ => tracing::info!("SUCCESS: Opened {} for streaming", key),
             }
+            video
+        }
+        Err(e) => {
+            tracing::error!("ERROR: Failed to open {}: {}", key, e);
+            outcome.fail(Stage::Download, e.to_string());
+            return outcome;
+        }
+    };
+    outcome.status = ItemStatus::Downloaded;
+    let downloaded = match &video {
+        VideoInput::File(path) => Some(path.clone()),
+        _ => None,
+    };
+    // Whisper is killed rather than let it run past the deadline
+    tracing::info!("Transcribing: {}", key);
+    let deadline = batch.chunks.deadline;
+    let mut timings = Timings::default();
+    let transcribed = tokio::time::timeout(
+        deadline.remaining(),
+        transcribe_key(&whisper, video, &batch.tscript_dir, key, &outputs, &batch.chunks, &mut timings),
+    )
+    .await;
+    // Steps that never ran have no duration
+    outcome.durations.decode_ms = Some(timings.decode_ms).filter(|ms| *ms > 0);
+    outcome.durations.transcribe_ms = Some(timings.transcribe_ms).filter(|ms| *ms > 0);
+    outcome.audio_ms = Some(timings.audio_ms).filter(|ms| *ms > 0);
+    if outcome.durations.decode_ms.is_some() {
+        outcome.status = ItemStatus::Decoded;
+    }
+    // Free /tmp for the next item
+    if let Some(path) = downloaded {
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            tracing::error!("ERROR: Failed to remove {}: {}", path.display(), e);
         }
     }
-    // List the videos in /tmp/videos/
-    let glob_pattern = "/tmp/videos/*.mp4".to_string();
-    for entry in glob(&glob_pattern).expect("ERROR: Failed to glob *.mp4 files") {
-        match entry {
-            Ok(video_path) => {
-                tracing::info!("Found video: {}", video_path.display());
+    let files = match transcribed {
+        Ok(Ok(transcribed)) => {
+            outcome.quality = Some(transcribed.quality);
+            transcribed.files
+        }
+        Ok(Err(e @ TranscribeError::Deadline { .. })) => {
+            tracing::info!("Deferring {}: {}", key, e);
+            outcome.defer(e.to_string());
+            return outcome;
+        }
+        Ok(Err(e)) => {
+            tracing::error!("ERROR: Failed to transcribe {}: {}", key, e);
+            outcome.fail(e.stage(), e.to_string());
+            return outcome;
+        }
+        Err(_) => {
+            tracing::info!("Deferring {}: deadline reached while transcribing", key);
+            outcome.defer("deadline reached while transcribing".to_string());
+            return outcome;
+        }
+    };
+    outcome.status = ItemStatus::Transcribed;
+    // Upload every format. A video only counts as uploaded once all of them are in S3
+    let started = Instant::now();
+    let mut failed: Vec<String> = vec![];
+    for file in files {
+        match put_transcript(batch.s3client, batch.tscript_bucket, &file.path, &file.key, file.format.content_type(), &stamp).await {
+            Ok(resp) => {
+                match resp.status {
+                    200 => outcome.outputs.push(resp.key),
+                    400 => failed.push(resp.key),
+                    _ => tracing::info!("ERROR: Unknown status for PutResponse")
+                }
+                tracing::info!("{}", resp.message);
             },
             Err(e) => {
-                tracing::info!("Failed to read glob entry. {}", e)
+                tracing::error!("ERROR: {}", e);
+                failed.push(file.key);
             }
         }
     }
-    // Transcribe videos in /tmp/videos/ --> /tmp/transcripts/
-    tracing::info!("Transcribing videos");
-    let output = Command::new("sh")
-        .arg("-c")
-        .arg("./transcribe.sh /tmp/videos")
-        .spawn()
-        .expect("ERROR: Failed to execute transcription command")
-        .wait()
-        .expect("ERROR: Failed to wait for transcription command");
-
-    // let stdout = String::from_utf8(output.stdout).expect("ERROR: Failed to convert stdout to String");
-    // let stderr = String::from_utf8(output.stderr).expect("ERROR: Failed to convert stderr to String");
-
-    // Get /tmp/transcripts/ paths
-    let glob_pattern = "/tmp/transcripts/**/video*.txt".to_string();
-    let mut processed_transcripts: Vec<String> = vec![];
-    let mut failed_transcripts: Vec<String> = vec![];
-    for entry in glob(&glob_pattern).expect("ERROR: Failed to glob *.txt files") {
-        match entry {
-            Ok(tscript_path) => {
-                // Upload to S3
-                match put_transcript(&s3client, &tscript_bucket, &tscript_path).await {
-                    Ok(resp) => {
-                        match resp.status {
-                            200 => processed_transcripts.push(resp.key),
-                            400 => failed_transcripts.push(resp.key),
-                            _ => tracing::info!("ERROR: Unknown status for PutResponse")
-                        }
-                        tracing::info!("{}", resp.message);
-                    },
-                    Err(e) => {
-                        tracing::error!("ERROR: {}", e);
-                    }
-                }
+    outcome.durations.upload_ms = elapsed_ms(started);
+    if failed.is_empty() {
+        outcome.status = ItemStatus::Uploaded;
+    } else {
+        outcome.fail(Stage::Upload, format!("Failed to upload {}", failed.join(", ")));
+    }
+    outcome
+}
+
+// Response for a batch where no item could start
+fn failed_batch(previous: PreviousResults, mut items: Vec<ItemOutcome>, failure: BatchFailure) -> TranscriberResponse {
+    let mut failed = previous.failed;
+    failed.extend(failure.failed);
+    items.extend(failure.items);
+    TranscriberResponse {
+        message: format!("ERROR: {}", failure.message),
+        processed: previous.processed,
+        failed,
+        items,
+        deferred: vec![],
+        redrives: previous.redrives,
+    }
+}
+
+async fn function_handler(config: &Config, event: LambdaEvent<S3Items>) -> Result<TranscriberResponse, Error> {
+    // Init S3 client
+    let s3client = init_s3client().await?;
+    let tscript_bucket = &config.transcript_bucket;
+    // Stop short of the invocation deadline so finished work is always reported
+    let deadline = Deadline::from_epoch_ms(event.context.deadline, config.deadline_reserve);
+    tracing::info!("{}s until deadline", deadline.remaining().as_secs());
+    // Process event payload
+    let mut previous = event.payload.previous.unwrap_or_default();
+    let mut items = event.payload.items;
+    let video_bucket = match &event.payload.batch_input {
+        Some(batch) => {
+            tracing::info!("Run {}: {} items", batch.run.run_id, items.len());
+            batch.run.bucket.clone()
+        }
+        None => config.video_bucket.clone(),
+    };
+    let run = event.payload.batch_input.as_ref().map(|b| &b.run);
+    // Outcomes of earlier passes, less the items deferred to this one
+    let mut outcomes: Vec<ItemOutcome> = std::mem::take(&mut previous.items);
+    outcomes.retain(|outcome| outcome.status != ItemStatus::Deferred);
+    let manifest_key = run.and_then(|r| r.manifest_key.as_ref());
+    let manifest = match manifest_key {
+        Some(manifest_key) => get_manifest(&s3client, &video_bucket, manifest_key)
+            .await
+            .map_err(|e| format!("Failed to load manifest {}: {}", manifest_key, e)),
+        None => Ok(Manifest::default()),
+    };
+    let mut skip = |item: &ItemDetails, reason: String| {
+        tracing::info!("Skipping {}: {}", item.key, reason);
+        outcomes.push(ItemOutcome::skipped(&item.key, reason));
+    };
+    // Markers, manifests and anything else that is not a video, going by the listing
+    items.retain(|item| match config.item_filter.check_listing(&item.key, item.size) {
+        Ok(()) => true,
+        Err(reason) => {
+            skip(item, reason);
+            false
+        }
+    });
+    let manifest = match manifest {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            // Without its manifest the run would take in every video under the prefix, and cleanup delete them
+            tracing::error!("ERROR: {}", e);
+            let options = run.map(|r| r.options.clone()).unwrap_or_default();
+            let keys = items.iter().map(|item| item.key.as_str());
+            let failure = BatchFailure::new(keys, &requested_formats(config, &options), Stage::Manifest, e);
+            return Ok(failed_batch(previous, outcomes, failure));
+        }
+    };
+    // Restrict the batch to videos listed in the run manifest
+    if let (Some(manifest_key), Some(run)) = (manifest_key, run) {
+        items.retain(|item| {
+            let listed = manifest.lists(&run.prefix, &item.key);
+            if !listed {
+                skip(item, format!("not in manifest {}", manifest_key));
+            }
+            listed
+        });
+    }
+    // Forced by the execution input or the manifest
+    let force = run.is_some_and(|r| r.force) || manifest.force;
+    // Execution input > manifest > config
+    let options = run.map(|r| r.options.clone()).unwrap_or_default().or(manifest.options);
+    let settings = match resolve(config, &options) {
+        Ok(settings) => settings,
+        Err(errors) => {
+            // Nothing in the batch can be transcribed with these options
+            tracing::error!(errors = ?errors, "Invalid run options");
+            let keys = items.iter().map(|item| item.key.as_str());
+            let message = format!("Invalid run options: {}", errors.join("; "));
+            let failure = BatchFailure::new(keys, &requested_formats(config, &options), Stage::Options, message);
+            return Ok(failed_batch(previous, outcomes, failure));
+        }
+    };
+    tracing::info!(
+        model = %settings.model.display(),
+        language = %settings.language,
+        translate = settings.translate,
+        decode = ?settings.decode,
+        formats = ?settings.formats,
+        "Run settings"
+    );
+    // Course vocabulary from the run options, else the nearest vocabulary.json above each video.
+    // A broken vocabulary only costs accuracy, so the videos still run without it
+    let vocabularies = match &options.vocabulary_key {
+        Some(key) => match get_vocabulary(&s3client, &video_bucket, key).await {
+            Ok(Some(vocabulary)) => {
+                tracing::info!("Vocabulary: {}", key);
+                Vocabularies::fixed(key.clone(), vocabulary)
+            }
+            Ok(None) => {
+                tracing::error!("ERROR: Failed to load vocabulary: {} does not exist", key);
+                Vocabularies::default()
             }
             Err(e) => {
-                tracing::info!("Failed to read glob entry. {}", e)
+                tracing::error!("ERROR: Failed to load vocabulary: {}", e);
+                Vocabularies::default()
+            }
+        },
+        None => {
+            let store = S3VocabularyStore { client: &s3client, bucket: &video_bucket };
+            let mut vocabularies = Vocabularies::default();
+            for item in &items {
+                if let Err(e) = vocabularies.resolve(&store, &item.key).await {
+                    tracing::error!("ERROR: Failed to load vocabulary for {}: {}", item.key, e);
+                }
             }
+            vocabularies
+        }
+    };
+    // Items run side by side as far as memory allows, sharing out the cores between their whisper runs
+    let model_bytes = std::fs::metadata(&settings.model).map(|m| m.len()).unwrap_or(0);
+    let vad_audio_ms = config.vad.map(|_| longest_decode_ms(config.chunking.chunk_ms, config.stream_videos));
+    let parallelism = plan(config.resources, item_memory_mb(model_bytes, vad_audio_ms), settings.decode.threads, config.max_parallel_items, items.len());
+    tracing::info!(
+        vcpus = config.resources.vcpus,
+        memory_mb = config.resources.memory_mb,
+        items = parallelism.items,
+        threads = parallelism.threads,
+        "Parallelism"
+    );
+    // Isolate this invocation from anything a warm container left in /tmp
+    let scratch = Scratch::create(Path::new(SCRATCH_ROOT), &event.context.request_id)?;
+    let whisper = Whisper {
+        bin: config.whisper_bin.clone(),
+        model: settings.model,
+        language: settings.language,
+        translate: settings.translate,
+        decode: DecodeParams { threads: parallelism.threads, ..settings.decode },
+        prompt: None,
+        vocabulary_key: None,
+        vad: config.vad,
+    };
+    // Chunk maps outlive the invocation so a deferred video resumes where it stopped
+    let store = S3ChunkStore { client: &s3client, bucket: tscript_bucket };
+    let batch = Batch {
+        s3client: &s3client,
+        video_bucket: &video_bucket,
+        tscript_bucket,
+        item_filter: &config.item_filter,
+        stream_videos: config.stream_videos,
+        force,
+        video_dir: scratch.videos(),
+        tscript_dir: scratch.transcripts(),
+        whisper,
+        outputs: Outputs::new(&settings.formats, config.caption_limits, Corrections::default(), config.quality),
+        vocabularies,
+        chunks: ChunkRun { chunking: config.chunking, store: &store, deadline },
+    };
+    // Videos are downloaded to {scratch}/videos/, or streamed, and transcribed to {scratch}/transcripts/,
+    // overlapping the downloads, decoding and inference of up to parallelism.items videos
+    let longest: Mutex<Duration> = Mutex::new(Duration::ZERO);
+    let results: Vec<(ItemDetails, ItemOutcome)> = stream::iter(items)
+        .map(|item| {
+            let (batch, longest) = (&batch, &longest);
+            async move {
+                // Slowest item so far, as the estimate for this one
+                let estimate = *longest.lock().unwrap();
+                if !deadline.allows(estimate) {
+                    let message = format!("{}s until deadline", deadline.remaining().as_secs());
+                    tracing::info!("Deferring {}: {}", item.key, message);
+                    let mut outcome = ItemOutcome::new(&item.key);
+                    outcome.defer(message);
+                    return (item, outcome);
+                }
+                let started = Instant::now();
+                let outcome = process_item(batch, &item).await;
+                let mut longest = longest.lock().unwrap();
+                *longest = (*longest).max(started.elapsed());
+                (item, outcome)
+            }
+        })
+        .buffered(parallelism.items)
+        .collect()
+        .await;
+    let mut processed_transcripts: Vec<String> = previous.processed;
+    let mut failed_transcripts: Vec<String> = previous.failed;
+    let mut deferred: Vec<ItemDetails> = vec![];
+    for (item, outcome) in results {
+        match outcome.status {
+            ItemStatus::Uploaded | ItemStatus::Current => processed_transcripts.extend(outcome.outputs.iter().cloned()),
+            ItemStatus::Deferred => deferred.push(item),
+            _ if outcome.failed() => failed_transcripts.extend(batch.outputs.formats.iter().map(|f| transcript_key(&item.key, f.ext()))),
+            _ => {}
+        }
+        outcomes.push(outcome);
+    }
+    // A video that never fits in an invocation would otherwise be re-driven forever
+    let mut redrives = previous.redrives;
+    if !deferred.is_empty() {
+        if redrives >= config.max_redrives {
+            tracing::error!("ERROR: Giving up on {} deferred items after {} re-drives", deferred.len(), redrives);
+            failed_transcripts.extend(deferred.drain(..).flat_map(|item| batch.outputs.formats.iter().map(move |f| transcript_key(&item.key, f.ext()))));
+            for outcome in outcomes.iter_mut().filter(|outcome| outcome.status == ItemStatus::Deferred) {
+                outcome.message = Some(format!("gave up after {} re-drives", redrives));
+            }
+        } else {
+            redrives += 1;
         }
     }
 
+    // Clean up scratch dir before the container is frozen
+    drop(scratch);
+
     // Response
+    let message = match deferred.len() {
+        0 => format!("DONE! Transcripts available in S3 Bucket: {}", tscript_bucket),
+        n => format!("DEFERRED {} items. Transcripts so far available in S3 Bucket: {}", n, tscript_bucket),
+    };
     let resp = TranscriberResponse {
-        message: format!("DONE! Transcripts available in S3 Bucket: {}", tscript_bucket),
+        message,
         processed: processed_transcripts,
-        failed: failed_transcripts
+        failed: failed_transcripts,
+        items: outcomes,
+        deferred,
+        redrives,
     };
 
     Ok(resp)
@@ -121,5 +504,21 @@ async fn main() -> Result<(), Error> {
         .with_target(false)
         .init();
 
-    run(service_fn(function_handler)).await
+    // Load config once per cold start
+    let config = match Config::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!(errors = ?e.errors, "Invalid configuration");
+            return Err(e.into());
+        }
+    };
+    // An AVX build on a CPU without AVX dies with SIGILL on its first instruction
+    tracing::info!(
+        cpu = ?config.cpu,
+        build = config.whisper_build.map(|level| level.suffix()).unwrap_or("WHISPER_BIN"),
+        bin = %config.whisper_bin.display(),
+        "Whisper build"
+    );
+    let config = &config;
+    run(service_fn(move |event| async move { function_handler(config, event).await })).await
 }
\ No newline at end of file
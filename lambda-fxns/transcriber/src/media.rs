@@ -0,0 +1,80 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::pin::Pin;
+use tokio::io::AsyncRead;
+
+// Bytes read from the start of an object to find its MP4 layout. ftyp and any free boxes are
+// tiny, so moov or mdat starts well inside this
+pub const HEADER_BYTES: u64 = 64 * 1024;
+
+// Where ffmpeg reads a video from
+pub enum VideoInput {
+    // Downloaded into the scratch dir
+    File(PathBuf),
+    // Presigned URL, read by ffmpeg with HTTP range requests so it can seek
+    Url(String),
+    // Object body piped into ffmpeg's stdin as it arrives. Read once, front to back
+    Stream(Pin<Box<dyn AsyncRead + Send>>),
+}
+
+impl VideoInput {
+    // Whether the input can be probed and read more than once i.e. chunk by chunk
+    pub fn seekable(&self) -> bool {
+        !matches!(self, VideoInput::Stream(_))
+    }
+
+    // ffmpeg -i argument
+    pub fn arg(&self) -> OsString {
+        match self {
+            VideoInput::File(path) => path.clone().into_os_string(),
+            VideoInput::Url(url) => OsString::from(url),
+            VideoInput::Stream(_) => OsString::from("pipe:0"),
+        }
+    }
+}
+
+// How a video's bytes are laid out, judged from its first bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    // moov before mdat, or not an MP4 at all. ffmpeg can decode it front to back
+    Sequential,
+    // mdat before moov. ffmpeg needs the index at the end before it can decode anything
+    MoovAtEnd,
+    // The header ran out, or is corrupt, before moov or mdat turned up
+    Unknown,
+}
+
+// Top level boxes that can start an MP4/QuickTime file
+const LEADING_BOXES: [&[u8; 4]; 8] = [b"ftyp", b"moov", b"mdat", b"free", b"skip", b"wide", b"pdin", b"uuid"];
+
+// Walk the top level MP4 boxes in the header until moov or mdat
+pub fn mp4_layout(header: &[u8]) -> Layout {
+    if header.len() < 8 || !LEADING_BOXES.iter().any(|b| &header[4..8] == *b) {
+        return Layout::Sequential;
+    }
+    let mut offset: usize = 0;
+    while offset + 8 <= header.len() {
+        let size = u32::from_be_bytes(header[offset..offset + 4].try_into().unwrap()) as u64;
+        let kind = &header[offset + 4..offset + 8];
+        match kind {
+            b"moov" => return Layout::Sequential,
+            b"mdat" => return Layout::MoovAtEnd,
+            _ => {}
+        }
+        // size 1: 64-bit size follows the type, size 0: box runs to the end of the file
+        let size = match size {
+            0 => return Layout::Unknown,
+            1 if offset + 16 <= header.len() => u64::from_be_bytes(header[offset + 8..offset + 16].try_into().unwrap()),
+            1 => return Layout::Unknown,
+            size => size,
+        };
+        if size < 8 {
+            return Layout::Unknown;
+        }
+        offset = match usize::try_from(size).ok().and_then(|size| offset.checked_add(size)) {
+            Some(next) => next,
+            None => return Layout::Unknown,
+        };
+    }
+    Layout::Unknown
+}
@@ -0,0 +1,265 @@
+use crate::captions::OutputFormat;
+use crate::config::Config;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+// Per-run settings from the execution input or the batch manifest. Unset fields fall back
+// to the next source: execution input > manifest > config
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct RunOptions {
+    // Transcript formats i.e. ["srt", "vtt"]
+    pub formats: Option<Vec<OutputFormat>>,
+    // Model baked into the image i.e. "small" --> models/ggml-small.bin
+    pub model: Option<String>,
+    // Spoken language i.e. "es", or "auto" to detect it
+    pub language: Option<String>,
+    // Translate the speech to English
+    pub translate: Option<bool>,
+    // Whisper decoding overrides i.e. {"beam_size": 8}
+    pub decode: Option<DecodeOptions>,
+    // Course vocabulary in the video bucket. Looked up next to the videos when absent
+    pub vocabulary_key: Option<String>,
+}
+
+impl RunOptions {
+    // Fill unset fields from a lower precedence source
+    pub fn or(self, fallback: RunOptions) -> RunOptions {
+        RunOptions {
+            formats: self.formats.filter(|f| !f.is_empty()).or(fallback.formats),
+            model: self.model.or(fallback.model),
+            language: self.language.or(fallback.language),
+            translate: self.translate.or(fallback.translate),
+            decode: match (self.decode, fallback.decode) {
+                (Some(decode), Some(fallback)) => Some(decode.or(fallback)),
+                (decode, fallback) => decode.or(fallback),
+            },
+            vocabulary_key: self.vocabulary_key.or(fallback.vocabulary_key),
+        }
+    }
+}
+
+// Validated settings for every video in a run
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunSettings {
+    pub formats: Vec<OutputFormat>,
+    pub model: PathBuf,
+    pub language: String,
+    pub translate: bool,
+    pub decode: DecodeParams,
+}
+
+// Whisper decoding parameters, passed to whisper.cpp and recorded in every transcript
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DecodeParams {
+    // -bs: beams kept during beam search
+    pub beam_size: u32,
+    // -bo: candidates sampled when falling back to a higher temperature
+    pub best_of: u32,
+    // -tp: initial sampling temperature
+    pub temperature: f32,
+    // -tpi: temperature increase when a segment fails the thresholds, 0 disables the fallback
+    pub temperature_inc: f32,
+    // -t: decoder threads
+    pub threads: u32,
+    // -ml: max segment length in characters, 0 for no limit
+    pub max_len: u32,
+    // -sns: suppress non-speech tokens i.e. (music), [applause]
+    pub suppress_non_speech: bool,
+    // -et: compression entropy above which a segment is decoded again
+    pub entropy_thold: f32,
+    // -lpt: average log probability below which a segment is decoded again
+    pub logprob_thold: f32,
+}
+
+// whisper.cpp defaults
+impl Default for DecodeParams {
+    fn default() -> Self {
+        DecodeParams {
+            beam_size: 5,
+            best_of: 5,
+            temperature: 0.0,
+            temperature_inc: 0.2,
+            threads: 4,
+            max_len: 0,
+            suppress_non_speech: false,
+            entropy_thold: 2.4,
+            logprob_thold: -1.0,
+        }
+    }
+}
+
+impl DecodeParams {
+    // whisper.cpp arguments for these parameters
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![
+            "-bs".to_string(), self.beam_size.to_string(),
+            "-bo".to_string(), self.best_of.to_string(),
+            "-tp".to_string(), self.temperature.to_string(),
+            "-tpi".to_string(), self.temperature_inc.to_string(),
+            "-t".to_string(), self.threads.to_string(),
+            "-ml".to_string(), self.max_len.to_string(),
+            "-et".to_string(), self.entropy_thold.to_string(),
+            "-lpt".to_string(), self.logprob_thold.to_string(),
+        ];
+        if self.suppress_non_speech {
+            args.push("-sns".to_string());
+        }
+        args
+    }
+
+    // Values whisper.cpp would reject or silently clamp. Decoders are capped at 8
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors: Vec<String> = vec![];
+        if !(1..=8).contains(&self.beam_size) {
+            errors.push(format!("beam_size {} must be between 1 and 8", self.beam_size));
+        }
+        if !(1..=8).contains(&self.best_of) {
+            errors.push(format!("best_of {} must be between 1 and 8", self.best_of));
+        }
+        if !(0.0..=1.0).contains(&self.temperature) {
+            errors.push(format!("temperature {} must be between 0 and 1", self.temperature));
+        }
+        if !(0.0..=1.0).contains(&self.temperature_inc) {
+            errors.push(format!("temperature_inc {} must be between 0 and 1", self.temperature_inc));
+        }
+        if self.threads == 0 {
+            errors.push("threads must be at least 1".to_string());
+        }
+        if !(self.entropy_thold.is_finite() && self.entropy_thold > 0.0) {
+            errors.push(format!("entropy_thold {} must be a positive number", self.entropy_thold));
+        }
+        if !(self.logprob_thold.is_finite() && self.logprob_thold <= 0.0) {
+            errors.push(format!("logprob_thold {} must be zero or negative", self.logprob_thold));
+        }
+        errors
+    }
+}
+
+// Per-run overrides of the configured decoding parameters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct DecodeOptions {
+    pub beam_size: Option<u32>,
+    pub best_of: Option<u32>,
+    pub temperature: Option<f32>,
+    pub temperature_inc: Option<f32>,
+    pub threads: Option<u32>,
+    pub max_len: Option<u32>,
+    pub suppress_non_speech: Option<bool>,
+    pub entropy_thold: Option<f32>,
+    pub logprob_thold: Option<f32>,
+}
+
+impl DecodeOptions {
+    pub fn or(self, fallback: DecodeOptions) -> DecodeOptions {
+        DecodeOptions {
+            beam_size: self.beam_size.or(fallback.beam_size),
+            best_of: self.best_of.or(fallback.best_of),
+            temperature: self.temperature.or(fallback.temperature),
+            temperature_inc: self.temperature_inc.or(fallback.temperature_inc),
+            threads: self.threads.or(fallback.threads),
+            max_len: self.max_len.or(fallback.max_len),
+            suppress_non_speech: self.suppress_non_speech.or(fallback.suppress_non_speech),
+            entropy_thold: self.entropy_thold.or(fallback.entropy_thold),
+            logprob_thold: self.logprob_thold.or(fallback.logprob_thold),
+        }
+    }
+
+    // Override the configured parameters with whatever was set for the run
+    pub fn apply(&self, params: DecodeParams) -> DecodeParams {
+        DecodeParams {
+            beam_size: self.beam_size.unwrap_or(params.beam_size),
+            best_of: self.best_of.unwrap_or(params.best_of),
+            temperature: self.temperature.unwrap_or(params.temperature),
+            temperature_inc: self.temperature_inc.unwrap_or(params.temperature_inc),
+            threads: self.threads.unwrap_or(params.threads),
+            max_len: self.max_len.unwrap_or(params.max_len),
+            suppress_non_speech: self.suppress_non_speech.unwrap_or(params.suppress_non_speech),
+            entropy_thold: self.entropy_thold.unwrap_or(params.entropy_thold),
+            logprob_thold: self.logprob_thold.unwrap_or(params.logprob_thold),
+        }
+    }
+}
+
+// whisper.cpp language codes i.e. en, es, zh, haw
+pub fn is_language(language: &str) -> bool {
+    static LANGUAGE: OnceLock<Regex> = OnceLock::new();
+    LANGUAGE.get_or_init(|| Regex::new(r"^(auto|[a-z]{2,3})$").unwrap()).is_match(language)
+}
+
+// Models available in the image, named as they are requested i.e. models/ggml-small.bin --> small
+pub fn available_models(models_dir: &Path) -> Vec<String> {
+    let mut models: Vec<String> = std::fs::read_dir(models_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| entry.file_name().to_str().map(|name| name.to_string()))
+                .filter_map(|name| Some(name.strip_prefix("ggml-")?.strip_suffix(".bin")?.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    models.sort();
+    models
+}
+
+// Model file for a requested name. "small", "ggml-small" and "ggml-small.bin" all name the same file
+pub fn model_path(models_dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let stem = name.strip_suffix(".bin").unwrap_or(name);
+    let stem = stem.strip_prefix("ggml-").unwrap_or(stem);
+    static MODEL_NAME: OnceLock<Regex> = OnceLock::new();
+    let re = MODEL_NAME.get_or_init(|| Regex::new(r"^[A-Za-z0-9][A-Za-z0-9._-]*$").unwrap());
+    let path = models_dir.join(format!("ggml-{}.bin", stem));
+    if !re.is_match(stem) || stem.contains("..") || !path.is_file() {
+        return Err(format!("model {} is not available (available: {})", name, available_models(models_dir).join(", ")));
+    }
+    Ok(path)
+}
+
+// English-only models i.e. ggml-base.en.bin can neither transcribe nor translate other languages
+fn is_english_only(model: &Path) -> bool {
+    model.file_stem().and_then(|s| s.to_str()).is_some_and(|s| s.ends_with(".en"))
+}
+
+// Transcript formats a run asks for. Known even when the rest of its options are invalid
+pub fn requested_formats(config: &Config, options: &RunOptions) -> Vec<OutputFormat> {
+    options.formats.clone().filter(|f| !f.is_empty()).unwrap_or_else(|| config.output_formats.clone())
+}
+
+// Resolve the options for a run against the config, reporting every invalid value
+pub fn resolve(config: &Config, options: &RunOptions) -> Result<RunSettings, Vec<String>> {
+    let mut errors: Vec<String> = vec![];
+    let models_dir = config.whisper_model.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let model = match &options.model {
+        Some(name) => model_path(models_dir, name).unwrap_or_else(|e| {
+            errors.push(e);
+            PathBuf::new()
+        }),
+        None => config.whisper_model.clone(),
+    };
+    let language = options.language.clone().unwrap_or_else(|| config.whisper_language.clone());
+    if !is_language(&language) {
+        errors.push(format!("language {} is not a language code or auto", language));
+    }
+    let translate = options.translate.unwrap_or(false);
+    let decode = options.decode.unwrap_or_default().apply(config.decode);
+    if errors.is_empty() && is_english_only(&model) && (language != "en" || translate) {
+        errors.push(format!(
+            "model {} is English-only, use a multilingual model for language {}{}",
+            model.display(),
+            language,
+            if translate { " with translate" } else { "" }
+        ));
+    }
+    errors.extend(decode.validate());
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(RunSettings {
+        formats: requested_formats(config, options),
+        model,
+        language,
+        translate,
+        decode,
+    })
+}
@@ -0,0 +1,62 @@
+// Memory kept back for the runtime, the transcriber itself and S3 buffers
+const RESERVED_MB: u64 = 512;
+
+// ffmpeg, decoded audio and whisper's working buffers on top of the model
+const ITEM_OVERHEAD_MB: u64 = 500;
+
+// 16kHz mono 16-bit PCM
+const PCM_BYTES_PER_MS: u64 = 32;
+
+// With VAD, the decoded audio is held twice at its peak: the raw PCM and its samples, then the samples and the speech
+// spliced out of them
+const VAD_COPIES: u64 = 2;
+
+// Longest audio budgeted for when a video is not split into chunks, i.e. 3 hours
+const UNCHUNKED_AUDIO_MS: u64 = 3 * 60 * 60 * 1000;
+
+// Below this many threads a whisper run slows down more than running another item gains
+const MIN_THREADS_PER_ITEM: u32 = 2;
+
+// What the function has to share between items
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Resources {
+    pub vcpus: u32,
+    pub memory_mb: u64,
+}
+
+// Items transcribed at once, and the whisper threads each of them gets
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Parallelism {
+    pub items: usize,
+    pub threads: u32,
+}
+
+// Memory one item needs. whisper.cpp holds about 1.3x the model file i.e. ggml-base.en.bin (141MB) --> 683MB,
+// plus the audio VAD buffers, if on, for the longest stretch of audio decoded at once
+pub fn item_memory_mb(model_bytes: u64, vad_audio_ms: Option<u64>) -> u64 {
+    let vad_mb = vad_audio_ms.map_or(0, |ms| ms * PCM_BYTES_PER_MS * VAD_COPIES / (1024 * 1024));
+    model_bytes / (1024 * 1024) * 13 / 10 + ITEM_OVERHEAD_MB + vad_mb
+}
+
+// Longest audio one decode reads: a chunk, unless chunking is off or videos are streamed and cannot be split
+pub fn longest_decode_ms(chunk_ms: u64, stream_videos: bool) -> u64 {
+    match chunk_ms {
+        0 => UNCHUNKED_AUDIO_MS,
+        _ if stream_videos => UNCHUNKED_AUDIO_MS,
+        chunk_ms => chunk_ms,
+    }
+}
+
+// Run as many items at once as fit in memory with at least MIN_THREADS_PER_ITEM cores each, then share
+// the cores between them so whisper never runs more threads than there are vCPUs. max_threads caps the
+// threads per item, max_items the items at once (0 for no cap)
+pub fn plan(resources: Resources, item_mb: u64, max_threads: u32, max_items: usize, pending: usize) -> Parallelism {
+    let by_cpu = (resources.vcpus / MIN_THREADS_PER_ITEM).max(1) as usize;
+    let by_memory = (resources.memory_mb.saturating_sub(RESERVED_MB) / item_mb.max(1)).max(1) as usize;
+    let mut items = by_cpu.min(by_memory).min(pending.max(1));
+    if max_items > 0 {
+        items = items.min(max_items);
+    }
+    let threads = (resources.vcpus / items as u32).clamp(1, max_threads.max(1));
+    Parallelism { items, threads }
+}
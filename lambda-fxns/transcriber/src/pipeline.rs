@@ -0,0 +1,578 @@
+use crate::captions::{render, CaptionLimits, OutputFormat};
+use crate::chunks::{shift, stitch, ChunkMap, ChunkStore, Chunking};
+use crate::deadline::Deadline;
+use crate::keys::{transcript_key, transcript_path};
+use crate::media::VideoInput;
+use crate::options::DecodeParams;
+use crate::quality::{check_quality, Quality, QualityLimits};
+use crate::report::Stage;
+use crate::vocabulary::Corrections;
+use crate::transcript::{words, Segment, Token, Transcript, TranscriptParams, TRANSCRIPT_VERSION};
+use crate::vad::{pcm_samples, samples_ms, speech_regions, splice, wav, Timeline, Vad};
+use serde::Deserialize;
+use std::fmt;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output, Stdio};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, ChildStdin, Command};
+
+// Whisper.cpp binary, model and language used for every video in an invocation
+#[derive(Clone)]
+pub struct Whisper {
+    pub bin: PathBuf,
+    pub model: PathBuf,
+    pub language: String,
+    pub translate: bool,
+    pub decode: DecodeParams,
+    // Initial prompt from the course vocabulary, and the key it was loaded from
+    pub prompt: Option<String>,
+    pub vocabulary_key: Option<String>,
+    // Only speech is transcribed when set
+    pub vad: Option<Vad>,
+}
+
+impl Whisper {
+    // Model name recorded in transcripts i.e. models/ggml-base.en.bin --> ggml-base.en
+    pub fn model_name(&self) -> String {
+        self.model.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+    }
+
+    // Settings recorded in transcripts
+    pub fn params(&self) -> TranscriptParams {
+        TranscriptParams {
+            language: self.language.clone(),
+            translate: self.translate,
+            decode: self.decode,
+            vocabulary_key: self.vocabulary_key.clone(),
+            prompt: self.prompt.clone(),
+            vad: self.vad,
+        }
+    }
+}
+
+// Transcript formats and caption limits requested for a run
+#[derive(Clone)]
+pub struct Outputs {
+    pub formats: Vec<OutputFormat>,
+    pub limits: CaptionLimits,
+    // Course vocabulary fixes applied before anything is rendered
+    pub corrections: Corrections,
+    // Hallucinated artifacts and loops found, and removed, before that
+    pub quality: QualityLimits,
+}
+
+impl Outputs {
+    // The JSON transcript is always uploaded first, the requested formats are rendered from it
+    pub fn new(formats: &[OutputFormat], limits: CaptionLimits, corrections: Corrections, quality: QualityLimits) -> Outputs {
+        let mut all = vec![OutputFormat::Json];
+        all.extend(formats.iter().filter(|f| **f != OutputFormat::Json));
+        Outputs {
+            formats: all,
+            limits,
+            corrections,
+            quality,
+        }
+    }
+}
+
+// Failure while transcribing a single video
+#[derive(Debug)]
+pub enum TranscribeError {
+    Spawn { program: String, message: String },
+    Probe(String),
+    Ffmpeg { status: String, stderr: String },
+    Whisper { status: String, stderr: String },
+    // SIGILL: the whisper build uses instructions this CPU does not have
+    IllegalInstruction { bin: String },
+    EmptyTranscript,
+    Parse(String),
+    Io(String),
+    // Out of time between chunks. Completed chunks are saved and resumed by the next invocation
+    Deadline { completed: usize, chunks: usize },
+}
+
+impl fmt::Display for TranscribeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscribeError::Spawn { program, message } => write!(f, "Failed to spawn {}: {}", program, message),
+            TranscribeError::Probe(message) => write!(f, "ffprobe failed: {}", message),
+            TranscribeError::Ffmpeg { status, stderr } => write!(f, "ffmpeg failed ({}): {}", status, stderr),
+            TranscribeError::Whisper { status, stderr } => write!(f, "whisper failed ({}): {}", status, stderr),
+            TranscribeError::IllegalInstruction { bin } => write!(f, "{} crashed with SIGILL (illegal instruction): it was built for CPU features this machine does not have", bin),
+            TranscribeError::EmptyTranscript => write!(f, "whisper produced an empty transcript"),
+            TranscribeError::Parse(message) => write!(f, "Failed to parse whisper output: {}", message),
+            TranscribeError::Io(message) => write!(f, "{}", message),
+            TranscribeError::Deadline { completed, chunks } => write!(f, "Deadline reached after {}/{} chunks", completed, chunks),
+        }
+    }
+}
+
+impl std::error::Error for TranscribeError {}
+
+impl TranscribeError {
+    // Pipeline step the error happened in
+    pub fn stage(&self) -> Stage {
+        match self {
+            TranscribeError::Spawn { program, .. } if program == "ffmpeg" || program == "ffprobe" => Stage::Decode,
+            TranscribeError::Probe(_) | TranscribeError::Ffmpeg { .. } => Stage::Decode,
+            _ => Stage::Transcribe,
+        }
+    }
+}
+
+// Audio decoded and time spent on it while transcribing a video, summed over its chunks
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Timings {
+    pub audio_ms: u64,
+    // ffmpeg, until whisper has read all of the audio
+    pub decode_ms: u64,
+    // whisper inference after that
+    pub transcribe_ms: u64,
+}
+
+const SIGILL: i32 = 4;
+
+// 16kHz mono 16-bit PCM
+const PCM_BYTES_PER_MS: u64 = 32;
+const WAV_HEADER_BYTES: u64 = 44;
+
+// Length of the audio in the WAV ffmpeg wrote i.e. 44 + 320000 bytes --> 10000ms
+pub fn wav_duration_ms(bytes: u64) -> u64 {
+    bytes.saturating_sub(WAV_HEADER_BYTES) / PCM_BYTES_PER_MS
+}
+
+// Rendered transcript file and the S3 key it is uploaded to
+pub struct TranscriptFile {
+    pub format: OutputFormat,
+    pub key: String,
+    pub path: PathBuf,
+}
+
+// How long audio is split, where its progress is kept and when to stop taking on more chunks
+pub struct ChunkRun<'a, S: ChunkStore> {
+    pub chunking: Chunking,
+    pub store: &'a S,
+    pub deadline: Deadline,
+}
+
+// whisper.cpp -ojf output. Only the detected language and timed segments are used
+#[derive(Deserialize)]
+struct WhisperJson {
+    #[serde(default)]
+    result: WhisperResult,
+    transcription: Vec<WhisperSegment>,
+}
+
+#[derive(Deserialize, Default)]
+struct WhisperResult {
+    #[serde(default)]
+    language: String,
+}
+
+#[derive(Deserialize)]
+struct WhisperSegment {
+    offsets: WhisperOffsets,
+    text: String,
+    #[serde(default)]
+    tokens: Vec<WhisperToken>,
+}
+
+#[derive(Deserialize)]
+struct WhisperToken {
+    id: i64,
+    text: String,
+    offsets: WhisperOffsets,
+    p: f32,
+}
+
+#[derive(Deserialize)]
+struct WhisperOffsets {
+    from: u64,
+    to: u64,
+}
+
+// Control tokens i.e. [_BEG_], [_TT_150] carry no speech
+fn is_special(text: &str) -> bool {
+    text.starts_with("[_") && text.ends_with(']')
+}
+
+// Transcript from whisper's full JSON output. whisper.cpp can split a multi-byte character
+// across tokens and write invalid UTF-8, so decode lossily rather than fail the whole video
+pub fn parse_whisper_json(json: &[u8], source_key: &str, model: &str) -> Result<Transcript, String> {
+    let json = String::from_utf8_lossy(json);
+    let parsed: WhisperJson = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    let segments = parsed.transcription
+        .into_iter()
+        .map(|s| {
+            let tokens = s.tokens
+                .into_iter()
+                .filter(|t| !is_special(&t.text))
+                .map(|t| Token {
+                    id: t.id,
+                    start_ms: t.offsets.from,
+                    end_ms: t.offsets.to,
+                    text: t.text,
+                    probability: t.p,
+                })
+                .collect();
+            Segment {
+                start_ms: s.offsets.from,
+                end_ms: s.offsets.to,
+                text: s.text.trim().to_string(),
+                words: words(tokens),
+            }
+        })
+        .collect();
+    Ok(Transcript {
+        version: TRANSCRIPT_VERSION,
+        source_key: source_key.to_string(),
+        model: model.to_string(),
+        language: parsed.result.language,
+        params: TranscriptParams::default(),
+        segments,
+    })
+}
+
+// Last n lines of a process' stderr, enough to diagnose a failure without flooding the logs
+fn stderr_tail(stderr: &[u8], n: usize) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+    let lines: Vec<&str> = stderr.lines().filter(|l| !l.trim().is_empty()).collect();
+    lines[lines.len().saturating_sub(n)..].join("\n")
+}
+
+fn check(status: ExitStatus, stderr: &[u8]) -> Result<(), (String, String)> {
+    if status.success() {
+        return Ok(());
+    }
+    Err((status.to_string(), stderr_tail(stderr, 20)))
+}
+
+// Length of the video's audio in milliseconds
+pub async fn probe_duration(video: &VideoInput) -> Result<u64, TranscribeError> {
+    if !video.seekable() {
+        return Err(TranscribeError::Probe("a stream cannot be probed".to_string()));
+    }
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(video.arg())
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| TranscribeError::Spawn { program: "ffprobe".to_string(), message: e.to_string() })?;
+    check(output.status, &output.stderr).map_err(|(status, stderr)| TranscribeError::Probe(format!("{}: {}", status, stderr)))?;
+    let duration = String::from_utf8_lossy(&output.stdout);
+    match duration.trim().parse::<f64>() {
+        Ok(secs) if secs.is_finite() && secs >= 0.0 => Ok((secs * 1000.0).round() as u64),
+        _ => Err(TranscribeError::Probe(format!("unexpected duration {}", duration.trim()))),
+    }
+}
+
+// whisper.cpp reading WAV from stdin --> {base}.json, with full JSON output including token timestamps and probabilities
+fn spawn_whisper(whisper: &Whisper, base: &Path) -> Result<Child, TranscribeError> {
+    let mut command = Command::new(&whisper.bin);
+    command.arg("-m")
+        .arg(&whisper.model)
+        .args(["-l", &whisper.language])
+        .args(whisper.decode.args())
+        .args(["-f", "-", "-ojf", "-of"])
+        .arg(base);
+    if whisper.translate {
+        command.arg("-tr");
+    }
+    if let Some(prompt) = &whisper.prompt {
+        command.args(["--prompt", prompt]);
+    }
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| TranscribeError::Spawn { program: whisper.bin.display().to_string(), message: e.to_string() })
+}
+
+fn check_whisper(whisper: &Whisper, output: &Output) -> Result<(), TranscribeError> {
+    if output.status.signal() == Some(SIGILL) {
+        return Err(TranscribeError::IllegalInstruction { bin: whisper.bin.display().to_string() });
+    }
+    check(output.status, &output.stderr).map_err(|(status, stderr)| TranscribeError::Whisper { status, stderr })
+}
+
+// Feed a streamed video to ffmpeg as it arrives, closing stdin at the end of the body
+async fn feed(video: &mut VideoInput, stdin: Option<ChildStdin>) -> std::io::Result<()> {
+    match (video, stdin) {
+        (VideoInput::Stream(body), Some(mut stdin)) => match tokio::io::copy(body, &mut stdin).await {
+            // ffmpeg stopped reading, its exit status says whether that was a problem
+            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+            result => result.map(|_| ()),
+        },
+        _ => Ok(()),
+    }
+}
+
+// ffmpeg | whisper, relaying the audio to measure how much there is and how long it takes to decode
+async fn pipe_to_whisper(whisper: &Whisper, key: &str, video: &mut VideoInput, base: &Path, mut ffmpeg: Child, started: Instant, timings: &mut Timings) -> Result<(), TranscribeError> {
+    let mut audio = ffmpeg.stdout
+        .take()
+        .ok_or_else(|| TranscribeError::Io("ffmpeg stdout not captured".to_string()))?;
+    let mut child = spawn_whisper(whisper, base)?;
+    let stdin = child.stdin.take();
+    let relay = async {
+        let relayed = match stdin {
+            Some(mut stdin) => tokio::io::copy(&mut audio, &mut stdin).await,
+            None => Err(std::io::Error::other("whisper stdin not captured")),
+        };
+        drop(audio);
+        (relayed, started.elapsed())
+    };
+    let inference = async {
+        let output = child.wait_with_output().await;
+        (output, started.elapsed())
+    };
+    // Wait on every end of the pipes, draining stderr so no process blocks
+    let stdin = ffmpeg.stdin.take();
+    let (fed, (relayed, decoded), ffmpeg, (output, finished)) = tokio::join!(feed(video, stdin), relay, ffmpeg.wait_with_output(), inference);
+    let ffmpeg = ffmpeg.map_err(|e| TranscribeError::Io(format!("Failed to wait for ffmpeg: {}", e)))?;
+    let output = output.map_err(|e| TranscribeError::Io(format!("Failed to wait for whisper: {}", e)))?;
+    timings.decode_ms += decoded.as_millis() as u64;
+    timings.transcribe_ms += finished.saturating_sub(decoded).as_millis() as u64;
+    // whisper exiting early breaks the pipe and takes ffmpeg down with it, so report whisper first
+    let whisper_first = matches!(&relayed, Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe);
+    if !whisper_first {
+        check(ffmpeg.status, &ffmpeg.stderr).map_err(|(status, stderr)| TranscribeError::Ffmpeg { status, stderr })?;
+    }
+    check_whisper(whisper, &output)?;
+    check(ffmpeg.status, &ffmpeg.stderr).map_err(|(status, stderr)| TranscribeError::Ffmpeg { status, stderr })?;
+    let relayed = relayed.map_err(|e| TranscribeError::Io(format!("Failed to pipe ffmpeg output to whisper: {}", e)))?;
+    timings.audio_ms += wav_duration_ms(relayed);
+    // A body cut short can still decode cleanly, so a failed read fails the video on its own
+    fed.map_err(|e| TranscribeError::Io(format!("Failed to stream {}: {}", key, e)))
+}
+
+// Decode all of the audio, then give whisper only the speech in it. None when there is no speech
+async fn speech_to_whisper(whisper: &Whisper, key: &str, video: &mut VideoInput, base: &Path, mut ffmpeg: Child, vad: &Vad, timings: &mut Timings) -> Result<Option<Timeline>, TranscribeError> {
+    let started = Instant::now();
+    let stdin = ffmpeg.stdin.take();
+    let (fed, ffmpeg) = tokio::join!(feed(video, stdin), ffmpeg.wait_with_output());
+    let ffmpeg = ffmpeg.map_err(|e| TranscribeError::Io(format!("Failed to wait for ffmpeg: {}", e)))?;
+    check(ffmpeg.status, &ffmpeg.stderr).map_err(|(status, stderr)| TranscribeError::Ffmpeg { status, stderr })?;
+    fed.map_err(|e| TranscribeError::Io(format!("Failed to stream {}: {}", key, e)))?;
+    let samples = pcm_samples(&ffmpeg.stdout);
+    // Only the samples are kept from here on
+    drop(ffmpeg);
+    let regions = speech_regions(&samples, vad);
+    timings.decode_ms += started.elapsed().as_millis() as u64;
+    timings.audio_ms += samples_ms(samples.len());
+    let speech_ms: u64 = regions.iter().map(|(start, end)| end - start).sum();
+    tracing::info!("Speech in {}: {}s of {}s in {} regions", key, speech_ms / 1000, samples_ms(samples.len()) / 1000, regions.len());
+    if regions.is_empty() {
+        return Ok(None);
+    }
+    let (speech, timeline) = splice(&samples, &regions);
+    drop(samples);
+    let started = Instant::now();
+    let mut child = spawn_whisper(whisper, base)?;
+    let stdin = child.stdin.take();
+    let write = async move {
+        match stdin {
+            Some(mut stdin) => stdin.write_all(&wav(&speech)).await,
+            None => Err(std::io::Error::other("whisper stdin not captured")),
+        }
+    };
+    let (written, output) = tokio::join!(write, child.wait_with_output());
+    let output = output.map_err(|e| TranscribeError::Io(format!("Failed to wait for whisper: {}", e)))?;
+    timings.transcribe_ms += started.elapsed().as_millis() as u64;
+    check_whisper(whisper, &output)?;
+    written.map_err(|e| TranscribeError::Io(format!("Failed to pipe speech to whisper: {}", e)))?;
+    Ok(Some(timeline))
+}
+
+// ffmpeg (16kHz mono PCM) | whisper --> {base}.json, optionally for just [start_ms, end_ms) of the audio.
+// Timestamps are relative to the start of the range
+pub async fn transcribe_video(whisper: &Whisper, key: &str, video: &mut VideoInput, base: &Path, range: Option<(u64, u64)>, timings: &mut Timings) -> Result<Transcript, TranscribeError> {
+    let started = Instant::now();
+    let mut ffmpeg = Command::new("ffmpeg");
+    ffmpeg.args(["-loglevel", "error"]);
+    // Seeking before the input is fast, and exact once decoded to PCM
+    if let Some((start_ms, _)) = range {
+        ffmpeg.args(["-ss", &seconds(start_ms)]);
+    }
+    ffmpeg.arg("-i").arg(video.arg());
+    if let Some((start_ms, end_ms)) = range {
+        ffmpeg.args(["-t", &seconds(end_ms.saturating_sub(start_ms))]);
+    }
+    // Raw samples when they are searched for speech before whisper gets them
+    let format = if whisper.vad.is_some() { "s16le" } else { "wav" };
+    let ffmpeg = ffmpeg
+        .args(["-f", format, "-ac", "1", "-acodec", "pcm_s16le", "-ar", "16000", "-"])
+        .stdin(if video.seekable() { Stdio::null() } else { Stdio::piped() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| TranscribeError::Spawn { program: "ffmpeg".to_string(), message: e.to_string() })?;
+    let model = whisper.model_name();
+    let params = whisper.params();
+    let timeline = match &whisper.vad {
+        Some(vad) => match speech_to_whisper(whisper, key, video, base, ffmpeg, vad, timings).await? {
+            Some(timeline) => Some(timeline),
+            // Nothing to transcribe, which only fails the video if none of it has speech
+            None => {
+                return Ok(Transcript {
+                    version: TRANSCRIPT_VERSION,
+                    source_key: key.to_string(),
+                    model,
+                    language: whisper.language.clone(),
+                    params,
+                    segments: vec![],
+                })
+            }
+        },
+        None => {
+            pipe_to_whisper(whisper, key, video, base, ffmpeg, started, timings).await?;
+            None
+        }
+    };
+    // whisper appends .json to the base it is given i.e. video0.whisper --> video0.whisper.json
+    let json = suffixed(base, ".json");
+    let json = tokio::fs::read(&json)
+        .await
+        .map_err(|e| TranscribeError::Io(format!("Failed to read {}: {}", json.display(), e)))?;
+    let mut transcript = parse_whisper_json(&json, key, &model).map_err(TranscribeError::Parse)?;
+    if let Some(timeline) = timeline {
+        timeline.remap(&mut transcript);
+    }
+    transcript.params = params;
+    Ok(transcript)
+}
+
+// Path with a suffix appended to its file name, keeping any extension it already has
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+// Milliseconds as ffmpeg seconds i.e. 90500 --> 90.500
+fn seconds(ms: u64) -> String {
+    format!("{}.{:03}", ms / 1000, ms % 1000)
+}
+
+// Transcribe long audio chunk by chunk, saving progress after each chunk. A map saved by an
+// earlier invocation with the same audio and settings is resumed from its last completed chunk
+async fn transcribe_chunked(whisper: &Whisper, key: &str, video: &mut VideoInput, base: &Path, fresh: ChunkMap, run: &ChunkRun<'_, impl ChunkStore>, timings: &mut Timings) -> Result<Transcript, TranscribeError> {
+    let store = run.store;
+    let mut map = match store.load(key).await {
+        Ok(Some(saved)) if saved.resumes(&fresh) => {
+            tracing::info!("Resuming {} from chunk {}/{}", key, saved.completed(), saved.chunks.len());
+            saved
+        }
+        Ok(_) => fresh,
+        Err(e) => {
+            tracing::error!("ERROR: Failed to load chunk map for {}: {}", key, e);
+            fresh
+        }
+    };
+    // Slowest chunk so far, as the estimate for the next one
+    let mut longest = Duration::ZERO;
+    for i in 0..map.chunks.len() {
+        if map.chunks[i].transcript.is_some() {
+            continue;
+        }
+        if !run.deadline.allows(longest) {
+            return Err(TranscribeError::Deadline { completed: map.completed(), chunks: map.chunks.len() });
+        }
+        let started = Instant::now();
+        let (start_ms, end_ms) = (map.chunks[i].start_ms, map.chunks[i].end_ms);
+        tracing::info!("Transcribing {} chunk {}/{} [{}, {}]", key, i + 1, map.chunks.len(), seconds(start_ms), seconds(end_ms));
+        let chunk_base = suffixed(base, &format!(".chunk{}", i));
+        let mut transcript = transcribe_video(whisper, key, &mut *video, &chunk_base, Some((start_ms, end_ms)), timings).await?;
+        shift(&mut transcript, start_ms);
+        map.chunks[i].transcript = Some(transcript);
+        // Losing a checkpoint only costs time if this invocation is cut short
+        if let Err(e) = store.save(key, &map).await {
+            tracing::error!("ERROR: Failed to save chunk map for {}: {}", key, e);
+        }
+        longest = longest.max(started.elapsed());
+    }
+    let transcript = stitch(&map).ok_or_else(|| TranscribeError::Io(format!("Chunk map for {} is incomplete", key)))?;
+    if let Err(e) = store.remove(key).await {
+        tracing::error!("ERROR: Failed to remove chunk map for {}: {}", key, e);
+    }
+    Ok(transcript)
+}
+
+// Render every requested format next to the whisper output i.e. {tscript_dir}/week1/lesson1/video0.{json,txt,srt,vtt}
+async fn write_outputs(tscript_dir: &Path, key: &str, transcript: &Transcript, outputs: &Outputs) -> Result<Vec<TranscriptFile>, TranscribeError> {
+    let mut files: Vec<TranscriptFile> = vec![];
+    for format in &outputs.formats {
+        let path = transcript_path(tscript_dir, key, format.ext()).map_err(TranscribeError::Io)?;
+        let rendered = render(*format, transcript, &outputs.limits)
+            .map_err(|e| TranscribeError::Io(format!("Failed to render {}: {}", path.display(), e)))?;
+        tokio::fs::write(&path, rendered)
+            .await
+            .map_err(|e| TranscribeError::Io(format!("Failed to write {}: {}", path.display(), e)))?;
+        files.push(TranscriptFile {
+            format: *format,
+            key: transcript_key(key, format.ext()),
+            path,
+        });
+    }
+    Ok(files)
+}
+
+// Rendered transcript files of a video, and how much of it looked like hallucination
+pub struct Transcribed {
+    pub files: Vec<TranscriptFile>,
+    pub quality: Quality,
+}
+
+// Transcribe a video key into the mirrored path under tscript_dir
+// i.e. week1/lesson1/video0.mp4 --> {tscript_dir}/week1/lesson1/video0.{json,txt,srt,vtt}
+pub async fn transcribe_key(whisper: &Whisper, mut video: VideoInput, tscript_dir: &Path, key: &str, outputs: &Outputs, run: &ChunkRun<'_, impl ChunkStore>, timings: &mut Timings) -> Result<Transcribed, TranscribeError> {
+    let chunking = &run.chunking;
+    // Raw whisper output is kept apart from the canonical {video}.json
+    let raw = transcript_path(tscript_dir, key, "whisper.json").map_err(TranscribeError::Io)?;
+    if let Some(dir) = raw.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| TranscribeError::Io(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+    let base = raw.with_extension("");
+    // Only long audio is worth probing and splitting, and only when it can be read more than once.
+    // If probing fails, ffmpeg gets a go at the whole file
+    let duration_ms = match chunking.chunk_ms {
+        0 => None,
+        _ if !video.seekable() => None,
+        _ => probe_duration(&video).await
+            .map_err(|e| tracing::error!("ERROR: Failed to probe {}: {}", key, e))
+            .ok(),
+    };
+    let mut transcript = match duration_ms {
+        Some(duration_ms) if chunking.enabled_for(duration_ms) => {
+            let fresh = ChunkMap::new(key, &whisper.model_name(), whisper.params(), duration_ms, *chunking);
+            let transcript = transcribe_chunked(whisper, key, &mut video, &base, fresh, run, timings).await;
+            // Overlaps are decoded twice, and resumed chunks not at all
+            timings.audio_ms = duration_ms;
+            transcript?
+        }
+        _ => transcribe_video(whisper, key, &mut video, &base, None, timings).await?,
+    };
+    let quality = check_quality(&mut transcript, &outputs.quality);
+    tracing::info!(
+        score = quality.score,
+        words = quality.words,
+        artifact_words = quality.artifact_words,
+        loop_words = quality.loop_words,
+        fast_segments = quality.fast_segments,
+        "Transcript quality of {}", key
+    );
+    // Never report an empty transcript as a success, including one that was all artifacts
+    if transcript.is_empty() {
+        return Err(TranscribeError::EmptyTranscript);
+    }
+    outputs.corrections.apply_transcript(&mut transcript);
+    let files = write_outputs(tscript_dir, key, &transcript, outputs).await?;
+    Ok(Transcribed { files, quality })
+}
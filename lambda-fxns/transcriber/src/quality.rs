@@ -0,0 +1,172 @@
+use crate::chunks::normalize;
+use crate::transcript::{Segment, Transcript};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+// Longest phrase looked for when whisper loops
+const MAX_LOOP_WORDS: usize = 8;
+
+// Segments shorter than this are timed as if they lasted this long, whisper's timestamps being coarse
+const MIN_SEGMENT_MS: u64 = 1000;
+
+// Markup whisper writes over audio without speech i.e. [BLANK_AUDIO], [Music], (upbeat music), ♪
+fn artifacts() -> &'static Regex {
+    static ARTIFACTS: OnceLock<Regex> = OnceLock::new();
+    ARTIFACTS.get_or_init(|| {
+        Regex::new(r"(?i)\[[^\]]*\]|\(\s*(?:[a-z]+\s+)?(?:music|applause|laughter|laughing|laughs|silence|noise|inaudible|blank[ _]audio|static|cheering|clapping|coughing|coughs|sighs)\s*\)|[♪♫]+").unwrap()
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualityLimits {
+    // Remove artifacts and loops from the transcript, rather than only count them
+    pub clean: bool,
+    // Segments spoken faster than this are flagged
+    pub max_words_per_sec: f32,
+    // Back to back repeats of a phrase that make a loop. A single word needs twice as many
+    pub min_repeats: usize,
+}
+
+impl Default for QualityLimits {
+    fn default() -> Self {
+        QualityLimits {
+            clean: true,
+            max_words_per_sec: 6.0,
+            min_repeats: 3,
+        }
+    }
+}
+
+// How much of a transcript looks like hallucination, reported per item i.e.
+// {"score": 0.82, "words": 120, "artifact_words": 4, "loop_words": 12, "fast_segments": 1, "fast_words": 6, "cleaned": true}
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Quality {
+    // Share of the words that are neither artifacts, loops nor spoken implausibly fast, 0 to 1
+    pub score: f32,
+    // Words whisper produced
+    pub words: usize,
+    pub artifact_words: usize,
+    // Repeats of a looped phrase, after its first occurrence
+    pub loop_words: usize,
+    pub fast_segments: usize,
+    pub fast_words: usize,
+    // Artifacts and loops were removed
+    pub cleaned: bool,
+}
+
+// Whitespace separated words of a segment's text and whether each one stays
+struct Marked {
+    words: Vec<String>,
+    keep: Vec<bool>,
+}
+
+fn mark_artifacts(segment: &Segment) -> Marked {
+    let matches: Vec<(usize, usize)> = artifacts().find_iter(&segment.text).map(|m| (m.start(), m.end())).collect();
+    let mut words: Vec<String> = vec![];
+    let mut keep: Vec<bool> = vec![];
+    let mut offset = 0;
+    for word in segment.text.split_whitespace() {
+        let start = offset + segment.text[offset..].find(word).unwrap_or(0);
+        let end = start + word.len();
+        offset = end;
+        words.push(word.to_string());
+        keep.push(!matches.iter().any(|(m_start, m_end)| *m_start <= start && end <= *m_end));
+    }
+    Marked { words, keep }
+}
+
+// Back to back repeats of words[i..i + n], counting the first
+fn repeats(words: &[String], i: usize, n: usize) -> usize {
+    let phrase = &words[i..i + n];
+    let mut count = 1;
+    while i + (count + 1) * n <= words.len() && words[i + count * n..i + (count + 1) * n] == *phrase {
+        count += 1;
+    }
+    count
+}
+
+// Positions of every repeat of a looped phrase after its first occurrence
+fn loop_repeats(words: &[String], min_repeats: usize) -> Vec<usize> {
+    let mut looped: Vec<usize> = vec![];
+    let mut i = 0;
+    while i < words.len() {
+        let found = (1..=MAX_LOOP_WORDS.min(words.len() - i)).find_map(|n| {
+            let needed = if n == 1 { min_repeats * 2 } else { min_repeats };
+            let count = repeats(words, i, n);
+            (count >= needed.max(2)).then_some((n, count))
+        });
+        match found {
+            Some((n, count)) => {
+                looped.extend(i + n..i + n * count);
+                i += n * count;
+            }
+            None => i += 1,
+        }
+    }
+    looped
+}
+
+// Remove the words not kept, keeping word timings in step with the text
+fn apply(segment: &mut Segment, marked: &Marked) {
+    if marked.keep.iter().all(|k| *k) {
+        return;
+    }
+    let text: Vec<&str> = marked.words.iter().zip(&marked.keep).filter(|(_, k)| **k).map(|(w, _)| w.as_str()).collect();
+    segment.text = text.join(" ");
+    if segment.words.len() == marked.words.len() {
+        let mut keep = marked.keep.iter();
+        segment.words.retain(|_| *keep.next().unwrap_or(&true));
+        if let (Some(first), Some(last)) = (segment.words.first(), segment.words.last()) {
+            segment.start_ms = first.start_ms;
+            segment.end_ms = last.end_ms;
+        }
+    }
+}
+
+// Find artifacts, loops and implausibly fast speech, removing the first two when limits.clean is set
+pub fn check_quality(transcript: &mut Transcript, limits: &QualityLimits) -> Quality {
+    let mut marked: Vec<Marked> = transcript.segments.iter().map(mark_artifacts).collect();
+    let words: usize = marked.iter().map(|m| m.words.len()).sum();
+    let artifact_words = marked.iter().flat_map(|m| &m.keep).filter(|k| !**k).count();
+    // Loops run across segment boundaries i.e. "Thank you." "Thank you." "Thank you."
+    let positions: Vec<(usize, usize)> = marked
+        .iter()
+        .enumerate()
+        .flat_map(|(s, m)| m.keep.iter().enumerate().filter(|(_, k)| **k).map(move |(w, _)| (s, w)))
+        .collect();
+    let flat: Vec<String> = positions.iter().map(|(s, w)| normalize(&marked[*s].words[*w])).collect();
+    let looped = loop_repeats(&flat, limits.min_repeats);
+    for i in &looped {
+        let (s, w) = positions[*i];
+        marked[s].keep[w] = false;
+    }
+    let mut quality = Quality {
+        words,
+        artifact_words,
+        loop_words: looped.len(),
+        cleaned: limits.clean,
+        ..Quality::default()
+    };
+    // Speech rate of what is left
+    for (segment, marked) in transcript.segments.iter().zip(&marked) {
+        let kept = marked.keep.iter().filter(|k| **k).count();
+        let secs = segment.end_ms.saturating_sub(segment.start_ms).max(MIN_SEGMENT_MS) as f32 / 1000.0;
+        if kept as f32 > limits.max_words_per_sec * secs {
+            quality.fast_segments += 1;
+            quality.fast_words += kept;
+        }
+    }
+    let flagged = quality.artifact_words + quality.loop_words + quality.fast_words;
+    quality.score = match words {
+        0 => 0.0,
+        _ => ((1.0 - flagged as f32 / words as f32).max(0.0) * 1000.0).round() / 1000.0,
+    };
+    if limits.clean {
+        for (segment, marked) in transcript.segments.iter_mut().zip(&marked) {
+            apply(segment, marked);
+        }
+        transcript.segments.retain(|segment| !segment.text.trim().is_empty());
+    }
+    quality
+}
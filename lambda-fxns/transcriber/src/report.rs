@@ -0,0 +1,156 @@
+use crate::captions::OutputFormat;
+use crate::keys::transcript_key;
+use crate::quality::Quality;
+use serde::{Deserialize, Serialize};
+
+// How far an item got. Each status implies the ones before it succeeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    Pending,
+    Downloaded,
+    Decoded,
+    Transcribed,
+    Uploaded,
+    // Transcripts made from the same video with the same model and settings were already uploaded
+    Current,
+    // Left out by the item filter, never downloaded
+    Skipped,
+    // Put off to the next invocation before the deadline
+    Deferred,
+}
+
+// Step an item failed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    // Loading the run manifest, before any item starts
+    Manifest,
+    // Resolving the run options against the config, before any item starts
+    Options,
+    Download,
+    Decode,
+    Transcribe,
+    Upload,
+}
+
+impl Stage {
+    // Status of an item that failed in this stage, the last step it completed
+    pub fn reached(self) -> ItemStatus {
+        match self {
+            Stage::Manifest | Stage::Options | Stage::Download => ItemStatus::Pending,
+            Stage::Decode => ItemStatus::Downloaded,
+            Stage::Transcribe => ItemStatus::Decoded,
+            Stage::Upload => ItemStatus::Transcribed,
+        }
+    }
+}
+
+// Wall time of each step, for the steps that ran
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct StepDurations {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decode_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transcribe_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload_ms: Option<u64>,
+}
+
+// What happened to one listed object in a run i.e.
+// {"key": "week1/video0.mp4", "status": "decoded", "error_stage": "transcribe", "message": "whisper exited with ...", ...}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemOutcome {
+    pub key: String,
+    pub status: ItemStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_stage: Option<Stage>,
+    // Why it failed or was skipped or deferred
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub durations: StepDurations,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_ms: Option<u64>,
+    // Transcript keys uploaded for it
+    #[serde(default)]
+    pub outputs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<Quality>,
+}
+
+// Longest message kept per item. Map results count towards the 256KB Step Functions payload limit, so a batch of
+// failures carrying whole stderr tails would fail the execution. The full message is in the transcriber logs
+pub const MAX_MESSAGE_BYTES: usize = 300;
+
+fn capped(mut message: String) -> String {
+    if message.len() > MAX_MESSAGE_BYTES {
+        let mut end = MAX_MESSAGE_BYTES - '…'.len_utf8();
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+        message.truncate(end);
+        message.push('…');
+    }
+    message
+}
+
+impl ItemOutcome {
+    pub fn new(key: &str) -> Self {
+        ItemOutcome {
+            key: key.to_string(),
+            status: ItemStatus::Pending,
+            error_stage: None,
+            message: None,
+            durations: StepDurations::default(),
+            audio_ms: None,
+            outputs: vec![],
+            quality: None,
+        }
+    }
+
+    pub fn skipped(key: &str, reason: String) -> Self {
+        ItemOutcome { status: ItemStatus::Skipped, message: Some(capped(reason)), ..ItemOutcome::new(key) }
+    }
+
+    // Record a failure, leaving the status at the last step completed
+    pub fn fail(&mut self, stage: Stage, message: String) {
+        self.status = stage.reached();
+        self.error_stage = Some(stage);
+        self.message = Some(capped(message));
+    }
+
+    pub fn defer(&mut self, message: String) {
+        self.status = ItemStatus::Deferred;
+        self.message = Some(capped(message));
+    }
+
+    pub fn failed(&self) -> bool {
+        self.error_stage.is_some()
+    }
+}
+
+// A batch that cannot start i.e. its manifest could not be loaded. Every item fails before it is downloaded
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchFailure {
+    pub message: String,
+    // Transcript keys of every requested format, as for an item that failed on its own
+    pub failed: Vec<String>,
+    pub items: Vec<ItemOutcome>,
+}
+
+impl BatchFailure {
+    pub fn new<'a>(keys: impl IntoIterator<Item = &'a str>, formats: &[OutputFormat], stage: Stage, message: String) -> Self {
+        let mut failed: Vec<String> = vec![];
+        let mut items: Vec<ItemOutcome> = vec![];
+        for key in keys {
+            failed.extend(formats.iter().map(|f| transcript_key(key, f.ext())));
+            let mut outcome = ItemOutcome::new(key);
+            outcome.fail(stage, message.clone());
+            items.push(outcome);
+        }
+        BatchFailure { message, failed, items }
+    }
+}
@@ -0,0 +1,46 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Root of all per-invocation scratch directories
+pub const SCRATCH_ROOT: &str = "/tmp/transcriber";
+
+// Per-invocation working directory, removed when dropped. Warm containers reuse /tmp,
+// so nothing from an earlier invocation may leak into this batch
+pub struct Scratch {
+    root: PathBuf,
+}
+
+impl Scratch {
+    // Create /tmp/transcriber/{request_id}/{videos,transcripts}, sweeping away any
+    // directories left behind by earlier invocations that were killed mid-run
+    pub fn create(base: &Path, request_id: &str) -> io::Result<Scratch> {
+        if base.exists() {
+            std::fs::remove_dir_all(base)?;
+        }
+        let name: String = request_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        let scratch = Scratch { root: base.join(name) };
+        std::fs::create_dir_all(scratch.videos())?;
+        std::fs::create_dir_all(scratch.transcripts())?;
+        Ok(scratch)
+    }
+
+    pub fn videos(&self) -> PathBuf {
+        self.root.join("videos")
+    }
+
+    pub fn transcripts(&self) -> PathBuf {
+        self.root.join("transcripts")
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        match std::fs::remove_dir_all(&self.root) {
+            Ok(_) => tracing::info!("Removed scratch dir {}", self.root.display()),
+            Err(e) => tracing::error!("ERROR: Failed to remove scratch dir {}: {}", self.root.display(), e),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::transcript::TranscriptParams;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+// S3 user metadata on every uploaded transcript i.e. x-amz-meta-source-etag
+pub const SOURCE_ETAG: &str = "source-etag";
+pub const MODEL: &str = "model";
+pub const SETTINGS_SHA256: &str = "settings-sha256";
+
+// What a transcript was made from, recorded with it so a re-run can tell whether it is still current
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stamp {
+    pub source_etag: String,
+    pub model: String,
+    pub settings_sha256: String,
+}
+
+// ETags are listed quoted i.e. "\"d9221b8c...\"" --> d9221b8c...
+pub fn normalize_etag(etag: &str) -> String {
+    etag.trim().trim_matches('"').to_string()
+}
+
+// SHA-256 of the settings that change the transcript, as lowercase hex
+pub fn settings_sha256(params: &TranscriptParams) -> String {
+    let settings = serde_json::to_vec(&params.settings()).unwrap_or_default();
+    Sha256::digest(&settings).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl Stamp {
+    pub fn new(source_etag: &str, model: &str, params: &TranscriptParams) -> Stamp {
+        Stamp {
+            source_etag: normalize_etag(source_etag),
+            model: model.to_string(),
+            settings_sha256: settings_sha256(params),
+        }
+    }
+
+    pub fn metadata(&self) -> HashMap<String, String> {
+        HashMap::from([
+            (SOURCE_ETAG.to_string(), self.source_etag.clone()),
+            (MODEL.to_string(), self.model.clone()),
+            (SETTINGS_SHA256.to_string(), self.settings_sha256.clone()),
+        ])
+    }
+
+    // None for transcripts uploaded before stamps were recorded
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> Option<Stamp> {
+        Some(Stamp {
+            source_etag: normalize_etag(metadata.get(SOURCE_ETAG)?),
+            model: metadata.get(MODEL)?.clone(),
+            settings_sha256: metadata.get(SETTINGS_SHA256)?.clone(),
+        })
+    }
+}
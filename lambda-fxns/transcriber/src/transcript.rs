@@ -0,0 +1,131 @@
+use crate::options::DecodeParams;
+use crate::vad::Vad;
+use serde::{Deserialize, Serialize};
+
+// Bumped whenever a field is removed or changes meaning. New optional fields keep the version
+pub const TRANSCRIPT_VERSION: u32 = 1;
+
+// Canonical transcript uploaded as {video}.json. Every other output format is rendered from it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transcript {
+    pub version: u32,
+    // Video key the transcript was produced from i.e. week1/lesson1/video0.mp4
+    pub source_key: String,
+    // Model file name without extension i.e. ggml-base.en
+    pub model: String,
+    // Spoken language detected or forced by whisper i.e. en
+    pub language: String,
+    // Settings whisper ran with
+    #[serde(default)]
+    pub params: TranscriptParams,
+    pub segments: Vec<Segment>,
+}
+
+// Whisper settings that produced a transcript
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptParams {
+    // Requested language, auto when whisper detected it
+    pub language: String,
+    pub translate: bool,
+    pub decode: DecodeParams,
+    // Course vocabulary and the initial prompt built from it
+    #[serde(default)]
+    pub vocabulary_key: Option<String>,
+    #[serde(default)]
+    pub prompt: Option<String>,
+    // Voice activity detection, when only speech was transcribed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vad: Option<Vad>,
+}
+
+impl TranscriptParams {
+    // Settings that change the transcript. Decoder threads are shared out per invocation and do not
+    pub fn settings(&self) -> TranscriptParams {
+        TranscriptParams {
+            decode: DecodeParams { threads: 0, ..self.decode },
+            ..self.clone()
+        }
+    }
+}
+
+// Timed segment of speech, offsets in milliseconds from the start of the video
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+    #[serde(default)]
+    pub words: Vec<Word>,
+}
+
+// Word built from one or more whisper tokens. Punctuation stays attached to its word
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Word {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+    // Mean probability of the word's tokens
+    pub probability: f32,
+    pub tokens: Vec<Token>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Token {
+    pub id: i64,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    // Raw token text, including the leading space that marks the start of a word
+    pub text: String,
+    pub probability: f32,
+}
+
+impl Transcript {
+    // Full text, one segment per line
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            let text = segment.text.trim();
+            if !text.is_empty() {
+                out.push_str(text);
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.iter().all(|s| s.text.trim().is_empty())
+    }
+}
+
+// Group tokens into words. A token starting with whitespace starts a new word, anything else
+// (sub-word pieces, punctuation) continues the current one
+pub fn words(tokens: Vec<Token>) -> Vec<Word> {
+    let mut words: Vec<Word> = vec![];
+    for token in tokens {
+        let starts_word = token.text.starts_with(char::is_whitespace);
+        match words.last_mut() {
+            Some(word) if !starts_word => {
+                word.text.push_str(&token.text);
+                word.end_ms = word.end_ms.max(token.end_ms);
+                word.tokens.push(token);
+            }
+            _ => {
+                if token.text.trim().is_empty() {
+                    continue;
+                }
+                words.push(Word {
+                    start_ms: token.start_ms,
+                    end_ms: token.end_ms,
+                    text: token.text.trim_start().to_string(),
+                    probability: 0.0,
+                    tokens: vec![token],
+                });
+            }
+        }
+    }
+    for word in &mut words {
+        word.probability = word.tokens.iter().map(|t| t.probability).sum::<f32>() / word.tokens.len() as f32;
+    }
+    words
+}
@@ -0,0 +1,173 @@
+use crate::transcript::Transcript;
+use serde::{Deserialize, Serialize};
+
+// 16kHz mono 16-bit PCM, as ffmpeg decodes it for whisper
+pub const SAMPLE_RATE: u32 = 16_000;
+const SAMPLES_PER_MS: usize = (SAMPLE_RATE / 1000) as usize;
+
+// Energy is measured over frames of this length
+const FRAME_MS: u64 = 30;
+
+// Silence left between spliced regions, so whisper does not run words of one region into the next
+const GAP_MS: u64 = 300;
+
+// Energy based voice activity detection. Frames louder than threshold_db are kept, so it skips silence but lets
+// music and other loud non-speech through
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Vad {
+    // Frame energy in dBFS i.e. -45.0
+    pub threshold_db: f32,
+    // Speech shorter than this is a click or a cough
+    pub min_speech_ms: u64,
+    // Silence shorter than this is a pause, and stays in
+    pub min_silence_ms: u64,
+    // Kept either side of speech, so soft word onsets and endings are not cut
+    pub pad_ms: u64,
+}
+
+impl Default for Vad {
+    fn default() -> Self {
+        Vad {
+            threshold_db: -45.0,
+            min_speech_ms: 250,
+            min_silence_ms: 1000,
+            pad_ms: 200,
+        }
+    }
+}
+
+// Little endian 16-bit samples
+pub fn pcm_samples(bytes: &[u8]) -> Vec<i16> {
+    bytes.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect()
+}
+
+pub fn samples_ms(samples: usize) -> u64 {
+    (samples / SAMPLES_PER_MS) as u64
+}
+
+// Root mean square level in dBFS, -inf for digital silence
+fn frame_db(frame: &[i16]) -> f32 {
+    let sum: f64 = frame.iter().map(|s| (*s as f64) * (*s as f64)).sum();
+    let rms = (sum / frame.len().max(1) as f64).sqrt();
+    (20.0 * (rms / 32768.0).log10()) as f32
+}
+
+// Join regions closer than gap_ms
+fn merge(regions: Vec<(u64, u64)>, gap_ms: u64) -> Vec<(u64, u64)> {
+    let mut merged: Vec<(u64, u64)> = vec![];
+    for (start, end) in regions {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + gap_ms => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+// [start_ms, end_ms) of the speech in the audio
+pub fn speech_regions(samples: &[i16], vad: &Vad) -> Vec<(u64, u64)> {
+    let total_ms = samples_ms(samples.len());
+    let frame_len = FRAME_MS as usize * SAMPLES_PER_MS;
+    let mut regions: Vec<(u64, u64)> = vec![];
+    for (i, frame) in samples.chunks(frame_len).enumerate() {
+        if frame_db(frame) < vad.threshold_db {
+            continue;
+        }
+        let start = i as u64 * FRAME_MS;
+        let end = (start + FRAME_MS).min(total_ms);
+        match regions.last_mut() {
+            Some(last) if last.1 == start => last.1 = end,
+            _ => regions.push((start, end)),
+        }
+    }
+    // Pauses stay in, then isolated blips go, then the rest is padded
+    let regions = merge(regions, vad.min_silence_ms)
+        .into_iter()
+        .filter(|(start, end)| end - start >= vad.min_speech_ms)
+        .map(|(start, end)| (start.saturating_sub(vad.pad_ms), (end + vad.pad_ms).min(total_ms)))
+        .collect();
+    merge(regions, 0)
+}
+
+// Where a stretch of the spliced audio came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub spliced_ms: u64,
+    pub original_ms: u64,
+    pub len_ms: u64,
+}
+
+// Maps timestamps in the spliced audio back to the original audio
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Timeline {
+    pub spans: Vec<Span>,
+}
+
+impl Timeline {
+    // Time in the original audio. Times in the silence between spans land at the end of the span before
+    pub fn original_ms(&self, spliced_ms: u64) -> u64 {
+        let i = self.spans.partition_point(|span| span.spliced_ms <= spliced_ms);
+        match i.checked_sub(1).map(|i| &self.spans[i]) {
+            Some(span) => span.original_ms + (spliced_ms - span.spliced_ms).min(span.len_ms),
+            None => spliced_ms,
+        }
+    }
+
+    pub fn remap(&self, transcript: &mut Transcript) {
+        for segment in &mut transcript.segments {
+            segment.start_ms = self.original_ms(segment.start_ms);
+            segment.end_ms = self.original_ms(segment.end_ms);
+            for word in &mut segment.words {
+                word.start_ms = self.original_ms(word.start_ms);
+                word.end_ms = self.original_ms(word.end_ms);
+                for token in &mut word.tokens {
+                    token.start_ms = self.original_ms(token.start_ms);
+                    token.end_ms = self.original_ms(token.end_ms);
+                }
+            }
+        }
+    }
+}
+
+// Only the regions of the audio, GAP_MS of silence apart, and the timeline back to the original
+pub fn splice(samples: &[i16], regions: &[(u64, u64)]) -> (Vec<i16>, Timeline) {
+    let mut spliced: Vec<i16> = vec![];
+    let mut timeline = Timeline::default();
+    for (i, (start_ms, end_ms)) in regions.iter().enumerate() {
+        if i > 0 {
+            spliced.resize(spliced.len() + GAP_MS as usize * SAMPLES_PER_MS, 0);
+        }
+        let start = (*start_ms as usize * SAMPLES_PER_MS).min(samples.len());
+        let end = (*end_ms as usize * SAMPLES_PER_MS).min(samples.len());
+        timeline.spans.push(Span {
+            spliced_ms: samples_ms(spliced.len()),
+            original_ms: *start_ms,
+            len_ms: end_ms - start_ms,
+        });
+        spliced.extend_from_slice(&samples[start..end]);
+    }
+    (spliced, timeline)
+}
+
+// Samples as a WAV file whisper reads from stdin
+pub fn wav(samples: &[i16]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, mono
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    out.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        out.extend_from_slice(&sample.to_le_bytes());
+    }
+    out
+}
@@ -0,0 +1,181 @@
+use crate::transcript::Transcript;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+
+// Per-course vocabulary stored next to the videos i.e. course-a/vocabulary.json
+pub const VOCABULARY_FILE: &str = "vocabulary.json";
+
+// Whisper's prompt is capped at half its 448 token text context. Stay well inside it
+const MAX_PROMPT_CHARS: usize = 600;
+
+// Terms whisper should expect and fixes for the ones it still gets wrong
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Vocabulary {
+    // Initial prompt. Built from terms when absent
+    pub prompt: Option<String>,
+    // Domain terms i.e. ["Rust", "tokio", "Step Functions"]
+    #[serde(default)]
+    pub terms: Vec<String>,
+    // Case-sensitive whole word replacements i.e. {"rust": "Rust"}
+    #[serde(default)]
+    pub corrections: BTreeMap<String, String>,
+}
+
+impl Vocabulary {
+    // Initial prompt for whisper, cut at a word boundary to fit its context
+    pub fn prompt(&self) -> Option<String> {
+        let prompt = match &self.prompt {
+            Some(prompt) => prompt.trim().to_string(),
+            None => self.terms.iter().map(|t| t.trim()).filter(|t| !t.is_empty()).collect::<Vec<&str>>().join(", "),
+        };
+        if prompt.is_empty() {
+            return None;
+        }
+        if prompt.chars().count() <= MAX_PROMPT_CHARS {
+            return Some(prompt);
+        }
+        let cut: String = prompt.chars().take(MAX_PROMPT_CHARS).collect();
+        let cut = match cut.rfind(char::is_whitespace) {
+            Some(i) => cut[..i].trim_end_matches([',', ' ']).to_string(),
+            None => cut,
+        };
+        Some(cut)
+    }
+
+    pub fn corrections(&self) -> Corrections {
+        Corrections::new(&self.corrections)
+    }
+}
+
+// Term corrections compiled into a single pass, longest term first so phrases win over the
+// words inside them and a replacement is never corrected again
+#[derive(Debug, Clone, Default)]
+pub struct Corrections {
+    re: Option<Regex>,
+    terms: BTreeMap<String, String>,
+}
+
+impl Corrections {
+    pub fn new(corrections: &BTreeMap<String, String>) -> Corrections {
+        let mut terms: Vec<&String> = corrections.keys().filter(|from| !from.trim().is_empty()).collect();
+        terms.sort_by_key(|from| std::cmp::Reverse(from.chars().count()));
+        // Only anchor on word boundaries where the term itself starts or ends with a word character i.e. C++
+        let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let patterns: Vec<String> = terms
+            .iter()
+            .map(|from| {
+                let start = if word(from.chars().next()) { r"\b" } else { "" };
+                let end = if word(from.chars().last()) { r"\b" } else { "" };
+                format!("{}{}{}", start, regex::escape(from), end)
+            })
+            .collect();
+        let re = (!patterns.is_empty()).then(|| Regex::new(&patterns.join("|")).unwrap());
+        Corrections {
+            re,
+            terms: corrections.clone(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.re.is_none()
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        match &self.re {
+            Some(re) => re.replace_all(text, |caps: &regex::Captures| self.terms[&caps[0]].clone()).into_owned(),
+            None => text.to_string(),
+        }
+    }
+
+    // Correct segment and word text. Raw token text is left as whisper produced it
+    pub fn apply_transcript(&self, transcript: &mut Transcript) {
+        if self.is_empty() {
+            return;
+        }
+        for segment in &mut transcript.segments {
+            segment.text = self.apply(&segment.text);
+            for word in &mut segment.words {
+                word.text = self.apply(&word.text);
+            }
+        }
+    }
+}
+
+// Where to look for the vocabulary of a video, nearest first
+// i.e. course-a/week1/lesson1/video0.mp4 --> course-a/week1/lesson1/vocabulary.json, course-a/week1/vocabulary.json,
+// course-a/vocabulary.json, vocabulary.json
+pub fn vocabulary_candidates(key: &str) -> Vec<String> {
+    let dirs: Vec<&str> = match key.rsplit_once('/') {
+        Some((dir, _)) => dir.split('/').collect(),
+        None => vec![],
+    };
+    (0..=dirs.len())
+        .rev()
+        .map(|n| match n {
+            0 => VOCABULARY_FILE.to_string(),
+            n => format!("{}/{}", dirs[..n].join("/"), VOCABULARY_FILE),
+        })
+        .collect()
+}
+
+// Where vocabularies are read from, keyed by vocabulary key. None when there is no such object
+pub trait VocabularyStore {
+    fn load(&self, key: &str) -> impl Future<Output = Result<Option<Vocabulary>, String>> + Send;
+}
+
+// Vocabulary of every video in a batch, looked up once per directory and loaded once per vocabulary key, so which
+// vocabulary a video gets never depends on the other videos batched with it
+#[derive(Debug, Default)]
+pub struct Vocabularies {
+    // Named by the run options, for every video
+    fixed: Option<(String, Vocabulary)>,
+    // Nearest vocabulary key of each video directory
+    nearest: BTreeMap<String, Option<String>>,
+    // Vocabularies read so far, None for keys with no object
+    loaded: BTreeMap<String, Option<Vocabulary>>,
+}
+
+impl Vocabularies {
+    pub fn fixed(key: String, vocabulary: Vocabulary) -> Vocabularies {
+        Vocabularies { fixed: Some((key, vocabulary)), ..Default::default() }
+    }
+
+    // Look up the nearest vocabulary of the video's directory, unless already known. A directory whose
+    // vocabulary fails to load goes without one
+    pub async fn resolve(&mut self, store: &impl VocabularyStore, key: &str) -> Result<(), String> {
+        let dir = key.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+        if self.fixed.is_some() || self.nearest.contains_key(dir) {
+            return Ok(());
+        }
+        for candidate in vocabulary_candidates(key) {
+            if !self.loaded.contains_key(&candidate) {
+                match store.load(&candidate).await {
+                    Ok(vocabulary) => self.loaded.insert(candidate.clone(), vocabulary),
+                    Err(e) => {
+                        self.nearest.insert(dir.to_string(), None);
+                        return Err(format!("{}: {}", candidate, e));
+                    }
+                };
+            }
+            if self.loaded[&candidate].is_some() {
+                self.nearest.insert(dir.to_string(), Some(candidate));
+                return Ok(());
+            }
+        }
+        self.nearest.insert(dir.to_string(), None);
+        Ok(())
+    }
+
+    // Vocabulary of a resolved video, with the key it was loaded from
+    pub fn get(&self, key: &str) -> Option<(&str, &Vocabulary)> {
+        if let Some((key, vocabulary)) = &self.fixed {
+            return Some((key, vocabulary));
+        }
+        let dir = key.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+        let candidate = self.nearest.get(dir)?.as_ref()?;
+        let vocabulary = self.loaded.get(candidate)?.as_ref()?;
+        Some((candidate, vocabulary))
+    }
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::segment_with_words;
+use transcriber::captions::{cues, parse_formats, render, render_srt, render_vtt, CaptionLimits, OutputFormat};
+use transcriber::pipeline::{parse_whisper_json, Outputs};
+use transcriber::transcript::Transcript;
+
+fn fixture() -> Transcript {
+    parse_whisper_json(include_bytes!("fixtures/whisper.json"), "week1/lesson1/video0.mp4", "ggml-base.en").unwrap()
+}
+
+#[test]
+fn parse_output_formats() {
+    assert_eq!(parse_formats("txt").unwrap(), vec![OutputFormat::Txt]);
+    assert_eq!(parse_formats(" srt, VTT ,srt").unwrap(), vec![OutputFormat::Srt, OutputFormat::Vtt]);
+    assert!(parse_formats("").is_err());
+    assert!(parse_formats("txt,pdf").is_err());
+    let formats: Vec<OutputFormat> = serde_json::from_str(r#"["vtt", "txt"]"#).unwrap();
+    assert_eq!(formats, vec![OutputFormat::Vtt, OutputFormat::Txt]);
+    // The JSON transcript is always written first
+    let outputs = Outputs::new(&[OutputFormat::Srt, OutputFormat::Json], CaptionLimits::default(), Default::default(), Default::default());
+    assert_eq!(outputs.formats, vec![OutputFormat::Json, OutputFormat::Srt]);
+}
+
+#[test]
+fn respect_line_limits() {
+    let limits = CaptionLimits { max_line_len: 20, max_lines: 2, max_cps: 100.0 };
+    let segments = fixture().segments;
+    let split = cues(&segments, &limits);
+    for cue in &split {
+        assert!(!cue.lines.is_empty() && cue.lines.len() <= 2, "{:?}", cue);
+        assert!(cue.lines.iter().all(|l| l.chars().count() <= 20), "{:?}", cue);
+        assert!(cue.start_ms < cue.end_ms, "{:?}", cue);
+    }
+    // No words are lost or reordered
+    let text: Vec<String> = split.iter().flat_map(|c| c.lines.clone()).collect();
+    let words: Vec<&str> = segments.iter().flat_map(|s| s.text.split_whitespace()).collect();
+    assert_eq!(text.join(" "), words.join(" "));
+    // Cues stay inside their segment and in order
+    assert_eq!(split.first().unwrap().start_ms, 0);
+    assert_eq!(split.last().unwrap().end_ms, 11000);
+    assert!(split.windows(2).all(|w| w[0].end_ms <= w[1].start_ms));
+    // Words longer than a line are kept whole
+    let long = cues(&[segment_with_words(0, 5000, "pneumonoultramicroscopicsilicovolcanoconiosis is long")], &limits);
+    assert_eq!(long[0].lines, vec!["pneumonoultramicroscopicsilicovolcanoconiosis", "is long"]);
+}
+
+#[test]
+fn extend_fast_cues() {
+    let limits = CaptionLimits { max_line_len: 42, max_lines: 2, max_cps: 10.0 };
+    // Case 0: 20 chars in 1s is extended into the following silence
+    let cues1 = cues(&[segment_with_words(0, 1000, "twenty chars of text"), segment_with_words(5000, 7000, "next")], &limits);
+    assert_eq!((cues1[0].start_ms, cues1[0].end_ms), (0, 2000));
+    assert_eq!((cues1[1].start_ms, cues1[1].end_ms), (5000, 7000));
+    // Case 1: time is borrowed from the next cue, which keeps enough time to be read
+    let cues2 = cues(&[segment_with_words(0, 1000, "twenty chars of text"), segment_with_words(1000, 3000, "next")], &limits);
+    assert_eq!((cues2[0].start_ms, cues2[0].end_ms), (0, 2000));
+    assert_eq!((cues2[1].start_ms, cues2[1].end_ms), (2000, 3000));
+    // Case 2: the next cue has no time to spare
+    let cues3 = cues(&[segment_with_words(0, 1000, "twenty chars of text"), segment_with_words(1000, 1400, "next")], &limits);
+    assert_eq!((cues3[0].start_ms, cues3[0].end_ms), (0, 1000));
+    assert_eq!((cues3[1].start_ms, cues3[1].end_ms), (1000, 1400));
+    // Case 3: the last cue is extended past the end of the audio
+    let cues4 = cues(&[segment_with_words(0, 1000, "twenty chars of text")], &limits);
+    assert_eq!(cues4[0].end_ms, 2000);
+}
+
+#[test]
+fn render_formats() {
+    let limits = CaptionLimits::default();
+    let segments = vec![segment_with_words(0, 2500, "Hello <world> & friends"), segment_with_words(3_725_100, 3_727_000, "Bye")];
+    let split = cues(&segments, &limits);
+    assert_eq!(
+        render_srt(&split),
+        "1\n00:00:00,000 --> 00:00:02,500\nHello <world> & friends\n\n2\n01:02:05,100 --> 01:02:07,000\nBye\n\n"
+    );
+    assert_eq!(
+        render_vtt(&split),
+        "WEBVTT\n\n00:00:00.000 --> 00:00:02.500\nHello &lt;world&gt; &amp; friends\n\n01:02:05.100 --> 01:02:07.000\nBye\n\n"
+    );
+}
+
+#[test]
+fn render_from_transcript() {
+    let transcript = fixture();
+    let limits = CaptionLimits::default();
+    assert_eq!(render(OutputFormat::Txt, &transcript, &limits).unwrap(), transcript.text());
+    let json = render(OutputFormat::Json, &transcript, &limits).unwrap();
+    assert_eq!(serde_json::from_str::<Transcript>(&json).unwrap(), transcript);
+    assert!(render(OutputFormat::Vtt, &transcript, &limits).unwrap().starts_with("WEBVTT\n\n00:00:00.000 --> "));
+}
@@ -0,0 +1,137 @@
+use std::sync::Mutex;
+use transcriber::chunks::{plan_chunks, shift, stitch, ChunkMap, ChunkStore, Chunking};
+use transcriber::transcript::{Segment, Token, Transcript, TranscriptParams, Word, TRANSCRIPT_VERSION};
+
+const MIN: u64 = 60 * 1000;
+
+fn transcript(segments: &[(u64, u64, &str)]) -> Transcript {
+    Transcript {
+        version: TRANSCRIPT_VERSION,
+        source_key: "week1/lesson1/video0.mp4".to_string(),
+        model: "ggml-base.en".to_string(),
+        language: "en".to_string(),
+        params: TranscriptParams::default(),
+        segments: segments
+            .iter()
+            .map(|(start_ms, end_ms, text)| Segment { start_ms: *start_ms, end_ms: *end_ms, text: text.to_string(), words: vec![] })
+            .collect(),
+    }
+}
+
+fn map(duration_ms: u64, chunking: Chunking) -> ChunkMap {
+    ChunkMap::new("week1/lesson1/video0.mp4", "ggml-base.en", TranscriptParams::default(), duration_ms, chunking)
+}
+
+// Chunk maps held in memory in place of S3
+#[derive(Default)]
+struct MemoryStore {
+    map: Mutex<Option<ChunkMap>>,
+}
+
+impl ChunkStore for MemoryStore {
+    async fn load(&self, _key: &str) -> Result<Option<ChunkMap>, String> {
+        Ok(self.map.lock().unwrap().clone())
+    }
+
+    async fn save(&self, _key: &str, map: &ChunkMap) -> Result<(), String> {
+        *self.map.lock().unwrap() = Some(map.clone());
+        Ok(())
+    }
+
+    async fn remove(&self, _key: &str) -> Result<(), String> {
+        *self.map.lock().unwrap() = None;
+        Ok(())
+    }
+}
+
+#[test]
+fn plan_overlapping_chunks() {
+    let chunking = Chunking { chunk_ms: 20 * MIN, overlap_ms: 30 * 1000 };
+    // Case 0: short audio is a single chunk
+    let chunks = plan_chunks(20 * MIN, &chunking);
+    assert_eq!(chunks.len(), 1);
+    assert_eq!((chunks[0].start_ms, chunks[0].end_ms), (0, 20 * MIN));
+    // Case 1: chunks overlap and the last one ends with the audio
+    let chunks = plan_chunks(50 * MIN, &chunking);
+    let ranges: Vec<(u64, u64)> = chunks.iter().map(|c| (c.start_ms, c.end_ms)).collect();
+    assert_eq!(ranges, vec![(0, 20 * MIN), (19 * MIN + 30_000, 39 * MIN + 30_000), (39 * MIN, 50 * MIN)]);
+    assert_eq!(chunks.iter().map(|c| c.index).collect::<Vec<usize>>(), vec![0, 1, 2]);
+    // Case 2: chunking disabled
+    let disabled = Chunking { chunk_ms: 0, overlap_ms: 0 };
+    assert!(!disabled.enabled_for(180 * MIN));
+    assert_eq!(plan_chunks(180 * MIN, &disabled).len(), 1);
+}
+
+#[test]
+fn shift_onto_source_timeline() {
+    let mut chunk = transcript(&[(1000, 2000, "hello")]);
+    chunk.segments[0].words = vec![Word {
+        start_ms: 1000,
+        end_ms: 2000,
+        text: "hello".to_string(),
+        probability: 0.9,
+        tokens: vec![Token { id: 1, start_ms: 1000, end_ms: 2000, text: " hello".to_string(), probability: 0.9 }],
+    }];
+    shift(&mut chunk, 60_000);
+    let segment = &chunk.segments[0];
+    assert_eq!((segment.start_ms, segment.end_ms), (61_000, 62_000));
+    assert_eq!((segment.words[0].start_ms, segment.words[0].tokens[0].end_ms), (61_000, 62_000));
+}
+
+#[test]
+fn stitch_overlap() {
+    let chunking = Chunking { chunk_ms: 60_000, overlap_ms: 10_000 };
+    let mut chunk_map = map(100_000, chunking);
+    assert_eq!(chunk_map.chunks.len(), 2);
+    // Both chunks hear 50-60s. The cut is at 55s
+    chunk_map.chunks[0].transcript = Some(transcript(&[
+        (0, 30_000, "Welcome to week one."),
+        (30_000, 54_000, "Today we cover ownership and borrowing"),
+        (54_000, 60_000, "in Rust with a"),
+    ]));
+    let mut second = transcript(&[
+        (0, 4_000, "ownership and borrowing."),
+        (3_000, 9_000, "and borrowing in Rust, with a few examples."),
+        (9_000, 50_000, "Let's start with ownership."),
+    ]);
+    shift(&mut second, 50_000);
+    chunk_map.chunks[1].transcript = Some(second);
+    let stitched = stitch(&chunk_map).unwrap();
+    // Case 0: segments either side of the cut are kept once, repeated words are dropped
+    assert_eq!(
+        stitched.text(),
+        "Welcome to week one.\nToday we cover ownership and borrowing\nin Rust, with a few examples.\nLet's start with ownership.\n"
+    );
+    // Case 1: timestamps never run backwards
+    let starts: Vec<u64> = stitched.segments.iter().map(|s| s.start_ms).collect();
+    assert_eq!(starts, vec![0, 30_000, 54_000, 59_000]);
+    assert!(stitched.segments.windows(2).all(|w| w[0].end_ms <= w[1].start_ms));
+    // Case 2: an unfinished chunk cannot be stitched
+    chunk_map.chunks[1].transcript = None;
+    assert!(stitch(&chunk_map).is_none());
+}
+
+#[tokio::test]
+async fn resume_chunk_map() {
+    let chunking = Chunking { chunk_ms: 60_000, overlap_ms: 10_000 };
+    let store = MemoryStore::default();
+    let mut saved = map(100_000, chunking);
+    saved.chunks[0].transcript = Some(transcript(&[(0, 60_000, "Welcome to week one.")]));
+    store.save("week1/lesson1/video0.mp4", &saved).await.unwrap();
+    let loaded = store.load("week1/lesson1/video0.mp4").await.unwrap().unwrap();
+    // Case 0: same audio and settings resume from the last completed chunk
+    assert!(loaded.resumes(&map(100_000, chunking)));
+    assert_eq!(loaded.completed(), 1);
+    let mut threads = TranscriptParams::default();
+    threads.decode.threads = 2;
+    assert!(loaded.resumes(&ChunkMap::new("week1/lesson1/video0.mp4", "ggml-base.en", threads, 100_000, chunking)));
+    // Case 1: anything that changes the chunks or the transcript starts over
+    assert!(!loaded.resumes(&map(120_000, chunking)));
+    assert!(!loaded.resumes(&map(100_000, Chunking { chunk_ms: 50_000, overlap_ms: 10_000 })));
+    let translated = TranscriptParams { translate: true, ..TranscriptParams::default() };
+    assert!(!loaded.resumes(&ChunkMap::new("week1/lesson1/video0.mp4", "ggml-base.en", translated, 100_000, chunking)));
+    assert!(!loaded.resumes(&ChunkMap::new("week1/lesson1/video0.mp4", "ggml-small", TranscriptParams::default(), 100_000, chunking)));
+    // Case 2: chunk maps round trip through JSON
+    let json = serde_json::to_string(&loaded).unwrap();
+    assert_eq!(serde_json::from_str::<ChunkMap>(&json).unwrap(), loaded);
+}
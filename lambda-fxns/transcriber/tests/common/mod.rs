@@ -0,0 +1,21 @@
+use transcriber::transcript::{words, Segment, Token};
+
+// Builders shared by the transcriber checks
+
+// Segment with one token per word, spread evenly over [start_ms, end_ms)
+pub fn segment_with_words(start_ms: u64, end_ms: u64, text: &str) -> Segment {
+    let texts: Vec<&str> = text.split_whitespace().collect();
+    let step = (end_ms - start_ms) / texts.len().max(1) as u64;
+    let tokens = texts
+        .iter()
+        .enumerate()
+        .map(|(i, t)| Token {
+            id: i as i64,
+            start_ms: start_ms + i as u64 * step,
+            end_ms: start_ms + (i as u64 + 1) * step,
+            text: format!(" {}", t),
+            probability: 0.9,
+        })
+        .collect();
+    Segment { start_ms, end_ms, text: format!(" {}", text), words: words(tokens) }
+}
@@ -0,0 +1,82 @@
+use lambda_config::testing::{lookup, temp_dir};
+use std::path::Path;
+use std::time::Duration;
+use transcriber::captions::OutputFormat;
+use transcriber::config::Config;
+
+// Image layout with a whisper build and a model i.e. {dir}/main-generic, {dir}/models/ggml-base.en.bin
+const IMAGE: &[&str] = &["main-generic", "models/ggml-base.en.bin"];
+
+fn load(dir: &Path, vars: &[(&str, &str)]) -> Result<Config, Vec<String>> {
+    let bin = dir.join("main").display().to_string();
+    let model = dir.join("models/ggml-base.en.bin").display().to_string();
+    let mut env = vec![("WHISPER_BIN", bin.as_str()), ("WHISPER_MODEL", model.as_str())];
+    env.extend_from_slice(vars);
+    Config::from_lookup(lookup(&env)).map_err(|e| e.errors)
+}
+
+#[test]
+fn load_transcriber_config() {
+    let dir = temp_dir("config-checks-load", IMAGE);
+    // Case 0: Defaults
+    let config = load(&dir, &[("VIDEO_BUCKET", "videos"), ("TRANSCRIPT_BUCKET", "transcripts")]).unwrap();
+    assert_eq!(config.video_bucket, "videos");
+    assert_eq!(config.transcript_bucket, "transcripts");
+    assert_eq!(config.whisper_bin, dir.join("main-generic"));
+    assert_eq!(config.whisper_language, "en");
+    assert_eq!(config.output_formats, vec![OutputFormat::Txt]);
+    assert_eq!(config.deadline_reserve, Duration::from_secs(60));
+    assert_eq!(config.max_redrives, 10);
+    assert!(config.vad.is_none());
+
+    // Case 1: Overrides, empty values fall back to defaults
+    let config = load(
+        &dir,
+        &[
+            ("VIDEO_BUCKET", "videos"),
+            ("TRANSCRIPT_BUCKET", "transcripts"),
+            ("OUTPUT_FORMATS", "srt,vtt"),
+            ("MAX_REDRIVES", "3"),
+            ("DEADLINE_RESERVE_SECS", ""),
+            ("VAD", "true"),
+        ],
+    )
+    .unwrap();
+    assert_eq!(config.output_formats, vec![OutputFormat::Srt, OutputFormat::Vtt]);
+    assert_eq!(config.max_redrives, 3);
+    assert_eq!(config.deadline_reserve, Duration::from_secs(60));
+    assert!(config.vad.is_some());
+}
+
+#[test]
+fn report_every_config_error() {
+    let dir = temp_dir("config-checks-errors", IMAGE);
+    // Case 0: Missing buckets
+    assert_eq!(load(&dir, &[]).unwrap_err(), vec!["VIDEO_BUCKET not set", "TRANSCRIPT_BUCKET not set"]);
+
+    // Case 1: All invalid values are reported together
+    let missing = dir.join("models/ggml-large.bin").display().to_string();
+    let errors = load(
+        &dir,
+        &[
+            ("VIDEO_BUCKET", "Videos"),
+            ("TRANSCRIPT_BUCKET", "transcripts"),
+            ("WHISPER_BIN", "/opt/whisper/main"),
+            ("WHISPER_MODEL", missing.as_str()),
+            ("MAX_REDRIVES", "-1"),
+            ("STREAM_VIDEOS", "yes"),
+            ("OUTPUT_FORMATS", "docx"),
+            ("MAX_VIDEO_MB", "18446744073709551615"),
+        ],
+    )
+    .unwrap_err();
+    assert_eq!(errors.len(), 7);
+    assert!(errors[0].starts_with("VIDEO_BUCKET"));
+    assert!(errors[1].starts_with("WHISPER_BIN"));
+    assert!(errors[2].starts_with("WHISPER_MODEL"));
+    assert!(errors.iter().any(|e| e.starts_with("MAX_REDRIVES")));
+    assert!(errors.iter().any(|e| e.starts_with("STREAM_VIDEOS")));
+    assert!(errors.iter().any(|e| e.starts_with("OUTPUT_FORMATS")));
+    // Case 2: Sizes that overflow in bytes are an error, not a panic
+    assert!(errors.contains(&"MAX_VIDEO_MB 18446744073709551615 is too large".to_string()));
+}
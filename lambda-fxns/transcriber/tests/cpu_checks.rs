@@ -0,0 +1,40 @@
+use std::path::{Path, PathBuf};
+use transcriber::cpu::{select_build, CpuFeatures, CpuLevel, WhisperBuild};
+
+const AVX2: CpuFeatures = CpuFeatures { avx: true, avx2: true, fma: true, f16c: true, avx512f: false, avx512bw: false, avx512vl: false, avx512dq: false, avx512cd: false };
+const AVX512: CpuFeatures = CpuFeatures { avx512f: true, avx512bw: true, avx512vl: true, avx512dq: true, avx512cd: true, ..AVX2 };
+
+#[test]
+fn level_from_features() {
+    assert_eq!(CpuFeatures::default().level(), CpuLevel::Generic);
+    assert_eq!(CpuFeatures { avx: true, ..CpuFeatures::default() }.level(), CpuLevel::Avx);
+    assert_eq!(AVX2.level(), CpuLevel::Avx2);
+    assert_eq!(AVX512.level(), CpuLevel::Avx512);
+    // Case 0: the AVX2 build also needs FMA and F16C
+    assert_eq!(CpuFeatures { f16c: false, ..AVX2 }.level(), CpuLevel::Avx);
+    assert_eq!(CpuFeatures { f16c: false, ..AVX512 }.level(), CpuLevel::Avx);
+    // Case 1: the AVX512 build needs every subset it is compiled with, else it can hit an illegal instruction
+    assert_eq!(CpuFeatures { avx512f: false, ..AVX512 }.level(), CpuLevel::Avx2);
+    assert_eq!(CpuFeatures { avx512bw: false, ..AVX512 }.level(), CpuLevel::Avx2);
+    assert_eq!(CpuFeatures { avx512vl: false, ..AVX512 }.level(), CpuLevel::Avx2);
+    assert_eq!(CpuFeatures { avx512dq: false, ..AVX512 }.level(), CpuLevel::Avx2);
+    assert_eq!(CpuFeatures { avx512cd: false, ..AVX512 }.level(), CpuLevel::Avx2);
+}
+
+#[test]
+fn select_whisper_build() {
+    let bin = Path::new("./main");
+    let image = |builds: &'static [&'static str]| move |path: &Path| builds.iter().any(|b| Path::new(b) == path);
+    let build = |bin: &str, level: Option<CpuLevel>| Some(WhisperBuild { bin: PathBuf::from(bin), level });
+    let all = image(&["./main-generic", "./main-avx", "./main-avx2", "./main-avx512"]);
+    // Case 0: the most capable build the CPU runs
+    assert_eq!(select_build(bin, CpuLevel::Avx512, all), build("./main-avx512", Some(CpuLevel::Avx512)));
+    assert_eq!(select_build(bin, CpuLevel::Avx2, all), build("./main-avx2", Some(CpuLevel::Avx2)));
+    assert_eq!(select_build(bin, CpuLevel::Generic, all), build("./main-generic", Some(CpuLevel::Generic)));
+    // Case 1: never a build above the CPU's level, falling back to the next one down
+    let partial = image(&["./main-generic", "./main-avx512"]);
+    assert_eq!(select_build(bin, CpuLevel::Avx2, partial), build("./main-generic", Some(CpuLevel::Generic)));
+    // Case 2: an image with a single build uses WHISPER_BIN as it is
+    assert_eq!(select_build(bin, CpuLevel::Avx2, image(&["./main"])), build("./main", None));
+    assert_eq!(select_build(bin, CpuLevel::Avx2, image(&[])), None);
+}
@@ -0,0 +1,29 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use transcriber::deadline::Deadline;
+
+#[test]
+fn reserve_time_before_deadline() {
+    // Case 0: the reserve comes off the time left
+    let deadline = Deadline::after(Duration::from_secs(900), Duration::from_secs(60));
+    assert!(deadline.remaining() <= Duration::from_secs(840));
+    assert!(deadline.remaining() > Duration::from_secs(830));
+    assert!(deadline.allows(Duration::ZERO));
+    assert!(deadline.allows(Duration::from_secs(600)));
+    assert!(!deadline.allows(Duration::from_secs(840)));
+    // Case 1: nothing is allowed once inside the reserve
+    let deadline = Deadline::after(Duration::from_secs(30), Duration::from_secs(60));
+    assert_eq!(deadline.remaining(), Duration::ZERO);
+    assert!(!deadline.allows(Duration::ZERO));
+}
+
+#[test]
+fn deadline_from_lambda_context() {
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+    // Case 0: milliseconds since the epoch, as in the Lambda context
+    let deadline = Deadline::from_epoch_ms(now_ms + 120_000, Duration::from_secs(20));
+    assert!(deadline.remaining() <= Duration::from_secs(100));
+    assert!(deadline.remaining() > Duration::from_secs(90));
+    // Case 1: a deadline already passed
+    let deadline = Deadline::from_epoch_ms(now_ms - 1_000, Duration::ZERO);
+    assert!(!deadline.allows(Duration::ZERO));
+}
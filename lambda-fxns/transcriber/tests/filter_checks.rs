@@ -0,0 +1,45 @@
+use regex::Regex;
+use transcriber::filter::{parse_extensions, ItemFilter};
+
+fn filter() -> ItemFilter {
+    ItemFilter {
+        extensions: parse_extensions("mp4, .MOV"),
+        skip_keys: Some(Regex::new("^archive/").unwrap()),
+        max_bytes: 1024 * 1024,
+    }
+}
+
+#[test]
+fn skip_by_listing() {
+    let filter = filter();
+    assert_eq!(filter.extensions, vec!["mp4", "mov"]);
+    // Case 0: videos, whatever the case of the extension
+    assert!(filter.check_listing("week1/lesson1/video0.mp4", 1000).is_ok());
+    assert!(filter.check_listing("week1/lesson1/video0.MOV", 1000).is_ok());
+    // Case 1: markers, manifests and stray files
+    for key in ["week1/done.txt", "done.txt", "week1/manifest.json", "course-a/vocabulary.json", "week1/notes"] {
+        assert!(filter.check_listing(key, 1000).is_err(), "{}", key);
+    }
+    assert_eq!(filter.check_listing("week1/", 0).unwrap_err(), "folder marker");
+    assert_eq!(filter.check_listing("week1/.mp4", 1000).unwrap_err(), "no file extension");
+    assert_eq!(filter.check_listing("week1/done.txt", 10).unwrap_err(), ".txt is not a media extension (mp4, mov)");
+    // Case 2: SKIP_KEYS
+    assert!(filter.check_listing("archive/week1/video0.mp4", 1000).is_err());
+    // Case 3: empty and oversized objects
+    assert_eq!(filter.check_listing("week1/video0.mp4", 0).unwrap_err(), "empty object");
+    assert!(filter.check_listing("week1/video0.mp4", 1024 * 1024).is_ok());
+    assert!(filter.check_listing("week1/video0.mp4", 1024 * 1024 + 1).is_err());
+    let unlimited = ItemFilter { max_bytes: 0, ..filter };
+    assert!(unlimited.check_listing("week1/video0.mp4", u64::MAX).is_ok());
+}
+
+#[test]
+fn skip_by_content_type() {
+    let filter = filter();
+    for content_type in [None, Some(""), Some("video/mp4"), Some("audio/mpeg"), Some("Video/QuickTime"), Some("binary/octet-stream"), Some("application/octet-stream; charset=binary")] {
+        assert!(filter.check_content_type(content_type).is_ok(), "{:?}", content_type);
+    }
+    assert_eq!(filter.check_content_type(Some("text/plain; charset=utf-8")).unwrap_err(), "content type text/plain is not audio or video");
+    assert!(filter.check_content_type(Some("application/json")).is_err());
+    assert!(filter.check_content_type(Some("image/png")).is_err());
+}
@@ -0,0 +1,42 @@
+use std::path::Path;
+use transcriber::keys::{path_key, transcript_key, transcript_path, video_path};
+
+#[test]
+fn map_transcript_keys() {
+    assert_eq!(transcript_key("week1/lesson1/video0.mp4", "txt"), "week1/lesson1/video0.txt");
+    assert_eq!(transcript_key("course-a/week1/lesson1/video0.mp4", "srt"), "course-a/week1/lesson1/video0.srt");
+    assert_eq!(transcript_key("video0.mp4", "txt"), "video0.txt");
+    // Only the file extension is replaced
+    assert_eq!(transcript_key("week1.5/lesson1/video0", "txt"), "week1.5/lesson1/video0.txt");
+    assert_eq!(transcript_key("week1/lesson1/video0.final.mp4", "txt"), "week1/lesson1/video0.final.txt");
+}
+
+#[test]
+fn round_trip_nested_keys() {
+    let videos = Path::new("/tmp/transcriber/req/videos");
+    let transcripts = Path::new("/tmp/transcriber/req/transcripts");
+    for key in [
+        "week1/lesson1/video0.mp4",
+        "course a/week03/lesson12/video7.mp4",
+        "video0.mp4",
+    ] {
+        let video = video_path(videos, key).unwrap();
+        assert_eq!(path_key(videos, &video).unwrap(), key);
+        let transcript = transcript_path(transcripts, key, "txt").unwrap();
+        assert_eq!(path_key(transcripts, &transcript).unwrap(), transcript_key(key, "txt"));
+    }
+    assert_eq!(
+        transcript_path(transcripts, "week1/lesson1/video0.mp4", "txt").unwrap(),
+        Path::new("/tmp/transcriber/req/transcripts/week1/lesson1/video0.txt")
+    );
+}
+
+#[test]
+fn reject_escaping_keys() {
+    let videos = Path::new("/tmp/transcriber/req/videos");
+    for key in ["", "/etc/passwd", "../video0.mp4", "week1/../../video0.mp4", "week1//video0.mp4", "week1/", "./video0.mp4"] {
+        assert!(video_path(videos, key).is_err(), "{}", key);
+        assert!(transcript_path(videos, key, "txt").is_err(), "{}", key);
+    }
+    assert_eq!(path_key(videos, Path::new("/tmp/other/video0.mp4")), None);
+}
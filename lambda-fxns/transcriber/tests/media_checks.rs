@@ -0,0 +1,43 @@
+use transcriber::media::{mp4_layout, Layout, VideoInput};
+
+// MP4 box header with a body of zeros
+fn mp4_box(kind: &[u8; 4], body_len: usize) -> Vec<u8> {
+    let mut out = ((body_len + 8) as u32).to_be_bytes().to_vec();
+    out.extend_from_slice(kind);
+    out.extend(vec![0; body_len]);
+    out
+}
+
+#[test]
+fn detect_mp4_layout() {
+    // Case 0: faststart MP4, moov before mdat
+    let header = [mp4_box(b"ftyp", 24), mp4_box(b"free", 8), mp4_box(b"moov", 512)].concat();
+    assert_eq!(mp4_layout(&header), Layout::Sequential);
+    // Case 1: moov written at the end
+    let header = [mp4_box(b"ftyp", 24), mp4_box(b"mdat", 4096)].concat();
+    assert_eq!(mp4_layout(&header), Layout::MoovAtEnd);
+    // Case 2: 64-bit box size before moov
+    let mut wide = 1u32.to_be_bytes().to_vec();
+    wide.extend_from_slice(b"free");
+    wide.extend_from_slice(&24u64.to_be_bytes());
+    wide.extend(vec![0; 8]);
+    let header = [mp4_box(b"ftyp", 24), wide, mp4_box(b"moov", 64)].concat();
+    assert_eq!(mp4_layout(&header), Layout::Sequential);
+    // Case 3: header ends, or is corrupt, before either box
+    assert_eq!(mp4_layout(&[mp4_box(b"ftyp", 24), mp4_box(b"free", 100_000)].concat()[..1024]), Layout::Unknown);
+    assert_eq!(mp4_layout(&[mp4_box(b"ftyp", 24), vec![0, 0, 0, 4], b"free".to_vec()].concat()), Layout::Unknown);
+    // Case 4: not an MP4 i.e. Matroska
+    assert_eq!(mp4_layout(&[0x1a, 0x45, 0xdf, 0xa3, 0x9f, 0x42, 0x86, 0x81, 0x01]), Layout::Sequential);
+}
+
+#[test]
+fn video_input_args() {
+    let file = VideoInput::File("/tmp/transcriber/req/videos/week1/video0.mp4".into());
+    assert!(file.seekable());
+    assert_eq!(file.arg(), "/tmp/transcriber/req/videos/week1/video0.mp4");
+    let url = VideoInput::Url("https://bucket.s3.amazonaws.com/week1/video0.mp4?X-Amz-Signature=abc".to_string());
+    assert!(url.seekable());
+    let stream = VideoInput::Stream(Box::pin(&b"not a video"[..]));
+    assert!(!stream.seekable());
+    assert_eq!(stream.arg(), "pipe:0");
+}
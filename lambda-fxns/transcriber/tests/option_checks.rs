@@ -0,0 +1,214 @@
+use lambda_config::testing::{lookup, temp_dir};
+use std::path::Path;
+use transcriber::captions::OutputFormat;
+use transcriber::config::Config;
+use transcriber::options::{available_models, is_language, model_path, resolve, DecodeOptions, DecodeParams, RunOptions};
+use transcriber::Manifest;
+
+// Image layout with a whisper binary and a few models i.e. {dir}/main, {dir}/models/ggml-*.bin
+const IMAGE: &[&str] = &["main", "models/ggml-base.en.bin", "models/ggml-small.bin", "models/ggml-medium.bin", "models/README.md"];
+
+fn load_config(dir: &Path, vars: &[(&str, &str)]) -> Config {
+    let bin = dir.join("main").display().to_string();
+    let model = dir.join("models/ggml-base.en.bin").display().to_string();
+    let mut env = vec![
+        ("VIDEO_BUCKET", "videos"),
+        ("TRANSCRIPT_BUCKET", "transcripts"),
+        ("WHISPER_BIN", bin.as_str()),
+        ("WHISPER_MODEL", model.as_str()),
+    ];
+    env.extend_from_slice(vars);
+    Config::from_lookup(lookup(&env)).unwrap()
+}
+
+#[test]
+fn check_languages() {
+    for language in ["en", "es", "zh", "haw", "auto"] {
+        assert!(is_language(language), "{}", language);
+    }
+    for language in ["", "EN", "english", "e", "en-US", "../en"] {
+        assert!(!is_language(language), "{}", language);
+    }
+}
+
+#[test]
+fn find_models() {
+    let dir = temp_dir("option-checks-models", IMAGE);
+    let models = dir.join("models");
+    assert_eq!(available_models(&models), vec!["base.en", "medium", "small"]);
+    for name in ["small", "ggml-small", "ggml-small.bin"] {
+        assert_eq!(model_path(&models, name).unwrap(), models.join("ggml-small.bin"));
+    }
+    // Missing models list what the image does have
+    let err = model_path(&models, "large-v3").unwrap_err();
+    assert_eq!(err, "model large-v3 is not available (available: base.en, medium, small)");
+    // Names can never point outside the models dir
+    for name in ["../main", "/etc/passwd", "", "small/../../main", ".hidden"] {
+        assert!(model_path(&models, name).is_err(), "{}", name);
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn resolve_run_options() {
+    let dir = temp_dir("option-checks-resolve", IMAGE);
+    let config = load_config(&dir, &[("OUTPUT_FORMATS", "txt,srt")]);
+    // Case 0: no options use the config defaults
+    let settings = resolve(&config, &RunOptions::default()).unwrap();
+    assert_eq!(settings.model, dir.join("models/ggml-base.en.bin"));
+    assert_eq!(settings.language, "en");
+    assert!(!settings.translate);
+    assert_eq!(settings.formats, vec![OutputFormat::Txt, OutputFormat::Srt]);
+    // Case 1: a multilingual model for Spanish, translated to English
+    let options = RunOptions {
+        model: Some("small".to_string()),
+        language: Some("es".to_string()),
+        translate: Some(true),
+        formats: Some(vec![OutputFormat::Vtt]),
+        decode: None,
+        vocabulary_key: None,
+    };
+    let settings = resolve(&config, &options).unwrap();
+    assert_eq!(settings.model, dir.join("models/ggml-small.bin"));
+    assert_eq!((settings.language.as_str(), settings.translate), ("es", true));
+    assert_eq!(settings.formats, vec![OutputFormat::Vtt]);
+    // Case 2: English-only models reject other languages and translation
+    for options in [
+        RunOptions { language: Some("zh".to_string()), ..Default::default() },
+        RunOptions { language: Some("auto".to_string()), ..Default::default() },
+        RunOptions { translate: Some(true), ..Default::default() },
+    ] {
+        let errors = resolve(&config, &options).unwrap_err();
+        assert!(errors[0].contains("English-only"), "{:?}", errors);
+    }
+    // Case 3: every invalid value is reported
+    let options = RunOptions {
+        model: Some("large-v3".to_string()),
+        language: Some("mandarin".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(resolve(&config, &options).unwrap_err().len(), 2);
+    // Case 4: WHISPER_LANGUAGE sets the default language
+    let config = load_config(&dir, &[("WHISPER_LANGUAGE", "auto")]);
+    let options = RunOptions { model: Some("medium".to_string()), ..Default::default() };
+    assert_eq!(resolve(&config, &options).unwrap().language, "auto");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn merge_run_options() {
+    // The execution input takes precedence over the manifest, field by field
+    let manifest: Manifest = serde_json::from_str(
+        r#"{"videos": ["week1/lesson1/video0.mp4"], "model": "small", "language": "es", "formats": ["srt"]}"#,
+    )
+    .unwrap();
+    assert_eq!(manifest.videos.as_ref().unwrap().len(), 1);
+    let run: RunOptions = serde_json::from_str(r#"{"language": "zh", "translate": true, "formats": []}"#).unwrap();
+    let merged = run.or(manifest.options);
+    assert_eq!(merged.model.as_deref(), Some("small"));
+    assert_eq!(merged.language.as_deref(), Some("zh"));
+    assert_eq!(merged.translate, Some(true));
+    assert_eq!(merged.formats, Some(vec![OutputFormat::Srt]));
+    // Manifests without options still parse
+    let manifest: Manifest = serde_json::from_str(r#"{"videos": null}"#).unwrap();
+    assert_eq!(manifest.options, RunOptions::default());
+    assert!(!manifest.force);
+    // The manifest can force re-transcription for runs started by done.txt
+    let manifest: Manifest = serde_json::from_str(r#"{"force": true, "model": "small"}"#).unwrap();
+    assert!(manifest.force);
+    assert_eq!(manifest.options.model.as_deref(), Some("small"));
+}
+
+#[test]
+fn match_manifest_videos() {
+    let manifest: Manifest = serde_json::from_str(r#"{"videos": ["lesson1/video0.mp4", "course-a/week1/lesson2/video0.mp4"]}"#).unwrap();
+    // Case 0: Entries relative to the run prefix, as in the README example
+    assert!(manifest.lists("course-a/week1/", "course-a/week1/lesson1/video0.mp4"));
+    assert!(!manifest.lists("course-a/week1/", "course-a/week1/lesson1/video1.mp4"));
+    // Case 1: Full keys, with or without a prefix
+    assert!(manifest.lists("course-a/week1/", "course-a/week1/lesson2/video0.mp4"));
+    assert!(manifest.lists("", "course-a/week1/lesson2/video0.mp4"));
+    // Case 2: A relative entry never matches the same path under another prefix
+    assert!(!manifest.lists("course-b/week1/", "course-a/week1/lesson1/video0.mp4"));
+    assert!(!manifest.lists("course-a/", "course-a/week1/lesson1/video0.mp4"));
+    // Case 3: Without a video list every item is in the batch
+    assert!(Manifest::default().lists("course-a/week1/", "course-a/week1/lesson3/video0.mp4"));
+}
+
+#[test]
+fn reject_invalid_language_config() {
+    let dir = temp_dir("option-checks-config", IMAGE);
+    let bin = dir.join("main").display().to_string();
+    let model = dir.join("models/ggml-base.en.bin").display().to_string();
+    let env = [
+        ("VIDEO_BUCKET", "videos"),
+        ("TRANSCRIPT_BUCKET", "transcripts"),
+        ("WHISPER_BIN", bin.as_str()),
+        ("WHISPER_MODEL", model.as_str()),
+        ("WHISPER_LANGUAGE", "English"),
+    ];
+    let err = Config::from_lookup(|name| env.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string())).unwrap_err();
+    assert_eq!(err.errors, vec!["WHISPER_LANGUAGE English is not a language code or auto"]);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn decode_params() {
+    let params = DecodeParams::default();
+    assert!(params.validate().is_empty());
+    assert_eq!(
+        params.args().join(" "),
+        "-bs 5 -bo 5 -tp 0 -tpi 0.2 -t 4 -ml 0 -et 2.4 -lpt -1"
+    );
+    let params = DecodeParams { suppress_non_speech: true, temperature_inc: 0.0, ..params };
+    assert!(params.args().ends_with(&["-lpt".to_string(), "-1".to_string(), "-sns".to_string()]));
+    let invalid = DecodeParams {
+        beam_size: 0,
+        best_of: 9,
+        temperature: 1.5,
+        temperature_inc: -0.1,
+        threads: 0,
+        max_len: 0,
+        suppress_non_speech: false,
+        entropy_thold: f32::NAN,
+        logprob_thold: 0.5,
+    };
+    assert_eq!(invalid.validate().len(), 7);
+}
+
+#[test]
+fn override_decode_params() {
+    let dir = temp_dir("option-checks-decode", IMAGE);
+    let config = load_config(&dir, &[("WHISPER_BEAM_SIZE", "8"), ("WHISPER_THREADS", "2"), ("WHISPER_SUPPRESS_NON_SPEECH", "true")]);
+    assert_eq!((config.decode.beam_size, config.decode.threads, config.decode.suppress_non_speech), (8, 2, true));
+    assert_eq!(config.decode.best_of, 5);
+    // Case 0: config defaults
+    assert_eq!(resolve(&config, &RunOptions::default()).unwrap().decode, config.decode);
+    // Case 1: the execution input overrides the manifest, which overrides the config
+    let manifest: RunOptions = serde_json::from_str(r#"{"decode": {"beam_size": 3, "temperature_inc": 0.0}}"#).unwrap();
+    let run: RunOptions = serde_json::from_str(r#"{"decode": {"beam_size": 1, "max_len": 60}}"#).unwrap();
+    let decode = resolve(&config, &run.or(manifest)).unwrap().decode;
+    assert_eq!(decode, DecodeParams { beam_size: 1, max_len: 60, temperature_inc: 0.0, ..config.decode });
+    // Case 2: invalid overrides fail the run
+    let options = RunOptions {
+        decode: Some(DecodeOptions { best_of: Some(0), ..Default::default() }),
+        ..Default::default()
+    };
+    assert_eq!(resolve(&config, &options).unwrap_err(), vec!["best_of 0 must be between 1 and 8"]);
+    // Case 3: invalid config defaults are reported at cold start
+    let bin = dir.join("main").display().to_string();
+    let model = dir.join("models/ggml-base.en.bin").display().to_string();
+    let env = [
+        ("VIDEO_BUCKET", "videos"),
+        ("TRANSCRIPT_BUCKET", "transcripts"),
+        ("WHISPER_BIN", bin.as_str()),
+        ("WHISPER_MODEL", model.as_str()),
+        ("WHISPER_BEAM_SIZE", "wide"),
+        ("WHISPER_LOGPROB_THOLD", "1"),
+    ];
+    let err = Config::from_lookup(|name| env.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string())).unwrap_err();
+    assert_eq!(err.errors.len(), 2);
+    assert!(err.errors[0].starts_with("WHISPER_BEAM_SIZE wide is invalid"));
+    assert_eq!(err.errors[1], "Invalid WHISPER_* default: logprob_thold 1 must be zero or negative");
+    std::fs::remove_dir_all(dir).unwrap();
+}
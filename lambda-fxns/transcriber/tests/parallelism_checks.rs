@@ -0,0 +1,44 @@
+use transcriber::parallelism::{item_memory_mb, longest_decode_ms, plan, Parallelism, Resources};
+
+const BASE_EN_BYTES: u64 = 147_951_465;
+const MEDIUM_BYTES: u64 = 1_533_763_059;
+
+#[test]
+fn estimate_item_memory() {
+    assert_eq!(item_memory_mb(BASE_EN_BYTES, None), 683);
+    assert_eq!(item_memory_mb(MEDIUM_BYTES, None), 2400);
+    // Unknown model size still leaves room for ffmpeg and the audio
+    assert_eq!(item_memory_mb(0, None), 500);
+    // Case 0: VAD holds two copies of the decoded audio, 20 minute chunks --> 73MB
+    let chunk_ms = 20 * 60 * 1000;
+    assert_eq!(longest_decode_ms(chunk_ms, false), chunk_ms);
+    assert_eq!(item_memory_mb(BASE_EN_BYTES, Some(longest_decode_ms(chunk_ms, false))), 756);
+    // Case 1: unchunked audio is budgeted at 3 hours --> 659MB
+    assert_eq!(longest_decode_ms(0, false), 3 * 60 * 60 * 1000);
+    assert_eq!(longest_decode_ms(chunk_ms, true), 3 * 60 * 60 * 1000);
+    assert_eq!(item_memory_mb(BASE_EN_BYTES, Some(longest_decode_ms(0, false))), 1342);
+}
+
+#[test]
+fn plan_items_and_threads() {
+    let lambda_max = Resources { vcpus: 6, memory_mb: 10240 };
+    // Case 0: cores are shared out, at least two threads each
+    assert_eq!(plan(lambda_max, item_memory_mb(BASE_EN_BYTES, None), 6, 0, 5), Parallelism { items: 3, threads: 2 });
+    // Case 1: memory caps the items, the spare cores go to whisper
+    let small = Resources { vcpus: 6, memory_mb: 5000 };
+    assert_eq!(plan(small, item_memory_mb(MEDIUM_BYTES, None), 6, 0, 5), Parallelism { items: 1, threads: 6 });
+    // Case 2: never more items than are pending, or than configured
+    assert_eq!(plan(lambda_max, item_memory_mb(BASE_EN_BYTES, None), 6, 0, 1), Parallelism { items: 1, threads: 6 });
+    assert_eq!(plan(lambda_max, item_memory_mb(BASE_EN_BYTES, None), 6, 2, 5), Parallelism { items: 2, threads: 3 });
+    // Case 3: threads never exceed the requested decoder threads
+    assert_eq!(plan(lambda_max, item_memory_mb(BASE_EN_BYTES, None), 1, 0, 5), Parallelism { items: 3, threads: 1 });
+    // Case 4: VAD buffers for unchunked audio leave room for fewer items
+    let vad_mb = item_memory_mb(BASE_EN_BYTES, Some(longest_decode_ms(0, false)));
+    assert_eq!(plan(lambda_max, vad_mb, 6, 0, 5), Parallelism { items: 3, threads: 2 });
+    let medium = Resources { vcpus: 6, memory_mb: 3008 };
+    assert_eq!(plan(medium, item_memory_mb(BASE_EN_BYTES, None), 6, 0, 5), Parallelism { items: 3, threads: 2 });
+    assert_eq!(plan(medium, vad_mb, 6, 0, 5), Parallelism { items: 1, threads: 6 });
+    // Case 5: a single core still runs one item
+    let tiny = Resources { vcpus: 1, memory_mb: 128 };
+    assert_eq!(plan(tiny, item_memory_mb(BASE_EN_BYTES, None), 4, 0, 5), Parallelism { items: 1, threads: 1 });
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::segment_with_words;
+use transcriber::quality::{check_quality, QualityLimits};
+use transcriber::transcript::{Segment, Transcript, TranscriptParams};
+
+fn transcript(segments: Vec<Segment>) -> Transcript {
+    Transcript {
+        version: 1,
+        source_key: "week1/video0.mp4".to_string(),
+        model: "ggml-base.en".to_string(),
+        language: "en".to_string(),
+        params: TranscriptParams::default(),
+        segments,
+    }
+}
+
+#[test]
+fn remove_artifacts() {
+    // Case 0: sound tags and blank audio are removed, with their words
+    let mut t = transcript(vec![
+        segment_with_words(0, 30000, "[BLANK_AUDIO]"),
+        segment_with_words(30000, 34000, "(upbeat music) Welcome to week one."),
+        segment_with_words(34000, 38000, "Today we cover ownership ♪"),
+    ]);
+    let quality = check_quality(&mut t, &QualityLimits::default());
+    assert_eq!((quality.words, quality.artifact_words, quality.loop_words), (12, 4, 0));
+    assert_eq!(quality.score, 0.667);
+    assert_eq!(t.text(), "Welcome to week one.\nToday we cover ownership\n");
+    // Word timings follow the text
+    assert_eq!(t.segments[0].words.len(), 4);
+    assert_eq!(t.segments[0].start_ms, 31332);
+    // Case 1: brackets around speech that is not a sound are left alone
+    let mut t = transcript(vec![segment_with_words(0, 4000, "Ownership (and borrowing) come next")]);
+    assert_eq!(check_quality(&mut t, &QualityLimits::default()).artifact_words, 0);
+}
+
+#[test]
+fn remove_loops() {
+    // Case 0: a phrase looping across segments keeps its first occurrence
+    let mut t = transcript(vec![
+        segment_with_words(0, 3000, "That is all for today."),
+        segment_with_words(3000, 4000, "Thank you."),
+        segment_with_words(4000, 5000, "Thank you."),
+        segment_with_words(5000, 6000, "thank you"),
+        segment_with_words(6000, 7000, "Thank you."),
+    ]);
+    let quality = check_quality(&mut t, &QualityLimits::default());
+    assert_eq!(quality.loop_words, 6);
+    assert_eq!(t.text(), "That is all for today.\nThank you.\n");
+    // Case 1: a loop inside a segment
+    let mut t = transcript(vec![segment_with_words(0, 10000, "so the borrow checker the borrow checker the borrow checker the borrow checker rejects it")]);
+    check_quality(&mut t, &QualityLimits::default());
+    assert_eq!(t.text(), "so the borrow checker rejects it\n");
+    assert_eq!(t.segments[0].words.len(), 6);
+    // Case 2: emphasis and a single repeat are not loops
+    let mut t = transcript(vec![segment_with_words(0, 5000, "this is very very very important, really important")]);
+    assert_eq!(check_quality(&mut t, &QualityLimits::default()).loop_words, 0);
+    let mut t = transcript(vec![segment_with_words(0, 3000, "Thank you."), segment_with_words(3000, 6000, "Thank you.")]);
+    assert_eq!(check_quality(&mut t, &QualityLimits::default()).loop_words, 0);
+}
+
+#[test]
+fn flag_fast_speech() {
+    let mut t = transcript(vec![
+        segment_with_words(0, 4000, "Ownership means every value has a single owner"),
+        segment_with_words(4000, 5000, "and when the owner goes out of scope the value is dropped right away"),
+    ]);
+    let quality = check_quality(&mut t, &QualityLimits::default());
+    assert_eq!((quality.fast_segments, quality.fast_words), (1, 14));
+    assert_eq!(quality.score, 0.364);
+    // Flagged, never removed
+    assert_eq!(t.segments.len(), 2);
+}
+
+#[test]
+fn report_without_cleaning() {
+    // Case 0: artifacts are counted but the transcript is left as is
+    let mut t = transcript(vec![segment_with_words(0, 3000, "[Music]"), segment_with_words(3000, 6000, "Welcome back.")]);
+    let before = t.clone();
+    let quality = check_quality(&mut t, &QualityLimits { clean: false, ..QualityLimits::default() });
+    assert_eq!(quality.artifact_words, 1);
+    assert!(!quality.cleaned);
+    assert_eq!(t, before);
+    // Case 1: nothing but artifacts cleans to an empty transcript
+    check_quality(&mut t, &QualityLimits::default());
+    assert_eq!(t.text(), "Welcome back.\n");
+    let mut t = transcript(vec![segment_with_words(0, 3000, "[BLANK_AUDIO]")]);
+    let quality = check_quality(&mut t, &QualityLimits::default());
+    assert_eq!(quality.score, 0.0);
+    assert!(t.is_empty());
+}
@@ -0,0 +1,107 @@
+use transcriber::captions::OutputFormat;
+use transcriber::pipeline::{wav_duration_ms, TranscribeError};
+use transcriber::report::{BatchFailure, ItemOutcome, ItemStatus, Stage, MAX_MESSAGE_BYTES};
+
+#[test]
+fn stage_of_errors() {
+    // Case 0: ffmpeg and ffprobe fail the decode
+    let spawn = |program: &str| TranscribeError::Spawn { program: program.to_string(), message: "No such file or directory".to_string() };
+    assert_eq!(spawn("ffmpeg").stage(), Stage::Decode);
+    assert_eq!(spawn("ffprobe").stage(), Stage::Decode);
+    assert_eq!(TranscribeError::Probe("unexpected duration N/A".to_string()).stage(), Stage::Decode);
+    // Case 1: whisper and everything after it fail the transcription
+    assert_eq!(spawn("/opt/whisper/main").stage(), Stage::Transcribe);
+    assert_eq!(TranscribeError::EmptyTranscript.stage(), Stage::Transcribe);
+    assert_eq!(TranscribeError::Parse("expected value".to_string()).stage(), Stage::Transcribe);
+}
+
+#[test]
+fn record_outcomes() {
+    // Case 0: a failure leaves the status at the last step completed
+    let mut outcome = ItemOutcome::new("week1/video0.mp4");
+    outcome.status = ItemStatus::Downloaded;
+    outcome.fail(Stage::Transcribe, "whisper exited with signal 9".to_string());
+    assert_eq!(outcome.status, ItemStatus::Decoded);
+    assert!(outcome.failed());
+    let mut outcome = ItemOutcome::new("week1/video0.mp4");
+    outcome.fail(Stage::Download, "NoSuchKey".to_string());
+    assert_eq!(outcome.status, ItemStatus::Pending);
+    // Case 1: skipped and deferred items are not failures
+    assert!(!ItemOutcome::skipped("week1/done.txt", "no file extension".to_string()).failed());
+    let mut outcome = ItemOutcome::new("week1/video1.mp4");
+    outcome.defer("30s until deadline".to_string());
+    assert_eq!(outcome.status, ItemStatus::Deferred);
+    assert!(!outcome.failed());
+}
+
+#[test]
+fn fail_whole_batch() {
+    // Case 0: a manifest that cannot be loaded fails every item, never lets the whole prefix through
+    let keys = ["week1/video0.mp4", "week1/video1.mov"];
+    let message = "Failed to load manifest week1/manifest.json: expected value at line 1 column 1".to_string();
+    let failure = BatchFailure::new(keys, &[OutputFormat::Srt, OutputFormat::Vtt], Stage::Manifest, message.clone());
+    assert_eq!(failure.items.len(), 2);
+    for (outcome, key) in failure.items.iter().zip(keys) {
+        assert_eq!(outcome.key, key);
+        assert_eq!(outcome.status, ItemStatus::Pending);
+        assert_eq!(outcome.error_stage, Some(Stage::Manifest));
+        assert_eq!(outcome.message.as_deref(), Some(message.as_str()));
+        assert!(outcome.failed());
+    }
+    // Case 1: every requested format is listed as failed, as for an item that failed on its own
+    assert_eq!(failure.failed, vec!["week1/video0.srt", "week1/video0.vtt", "week1/video1.srt", "week1/video1.vtt"]);
+    assert_eq!(serde_json::to_value(&failure.items[0]).unwrap()["error_stage"], "manifest");
+
+    // Case 2: invalid run options fail the batch the same way, before any item is downloaded
+    let failure = BatchFailure::new(["week1/video0.mp4"], &[OutputFormat::Json, OutputFormat::Srt], Stage::Options, "Invalid run options: language xx-yy is not a language code or auto".to_string());
+    assert_eq!(failure.items[0].status, ItemStatus::Pending);
+    assert_eq!(failure.items[0].error_stage, Some(Stage::Options));
+    assert_eq!(failure.failed, vec!["week1/video0.json", "week1/video0.srt"]);
+    assert_eq!(serde_json::to_value(&failure.items[0]).unwrap()["error_stage"], "options");
+}
+
+#[test]
+fn serialize_outcomes() {
+    // Case 0: only the steps that ran are written out
+    let mut outcome = ItemOutcome::new("week1/video0.mp4");
+    outcome.status = ItemStatus::Downloaded;
+    outcome.durations.download_ms = Some(1200);
+    outcome.fail(Stage::Decode, "ffmpeg exited with exit status: 1".to_string());
+    let json = serde_json::to_value(&outcome).unwrap();
+    assert_eq!(json["status"], "downloaded");
+    assert_eq!(json["error_stage"], "decode");
+    assert_eq!(json["durations"], serde_json::json!({ "download_ms": 1200 }));
+    assert!(json.get("audio_ms").is_none());
+    // Case 1: round trip, as previous results come back in on a re-drive
+    assert_eq!(serde_json::from_value::<ItemOutcome>(json).unwrap(), outcome);
+    let minimal: ItemOutcome = serde_json::from_str(r#"{"key": "week1/video0.mp4", "status": "uploaded"}"#).unwrap();
+    assert_eq!(minimal.status, ItemStatus::Uploaded);
+    assert!(minimal.outputs.is_empty());
+}
+
+#[test]
+fn audio_length_from_wav_bytes() {
+    assert_eq!(wav_duration_ms(44 + 320_000), 10_000);
+    assert_eq!(wav_duration_ms(44), 0);
+    assert_eq!(wav_duration_ms(0), 0);
+}
+
+#[test]
+fn cap_item_messages() {
+    // Case 0: Short messages are kept whole
+    let mut outcome = ItemOutcome::new("week1/video0.mp4");
+    outcome.fail(Stage::Download, "NoSuchKey".to_string());
+    assert_eq!(outcome.message.as_deref(), Some("NoSuchKey"));
+    // Case 1: A long stderr tail is cut to the cap on a char boundary, so a batch of failures stays under the payload limit
+    let stderr = "whisper_init_from_file: failed to load model — ".repeat(20);
+    outcome.fail(Stage::Transcribe, format!("whisper failed (exit status: 1): {}", stderr));
+    let message = outcome.message.unwrap();
+    assert!(message.len() <= MAX_MESSAGE_BYTES);
+    assert!(message.starts_with("whisper failed (exit status: 1): whisper_init_from_file"));
+    assert!(message.ends_with('…'));
+    // Case 2: Skipped and deferred items are capped the same way
+    assert!(ItemOutcome::skipped("week1/video1.mp4", "x".repeat(1000)).message.unwrap().len() <= MAX_MESSAGE_BYTES);
+    let mut deferred = ItemOutcome::new("week1/video2.mp4");
+    deferred.defer("y".repeat(1000));
+    assert!(deferred.message.unwrap().len() <= MAX_MESSAGE_BYTES);
+}
@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+use transcriber::options::DecodeParams;
+use transcriber::stamp::{normalize_etag, settings_sha256, Stamp};
+use transcriber::transcript::TranscriptParams;
+
+fn params() -> TranscriptParams {
+    TranscriptParams {
+        language: "en".to_string(),
+        translate: false,
+        decode: DecodeParams::default(),
+        vocabulary_key: Some("course-a/vocabulary.json".to_string()),
+        prompt: Some("Ferris, borrow checker".to_string()),
+        vad: None,
+    }
+}
+
+#[test]
+fn hash_settings() {
+    let hash = settings_sha256(&params());
+    assert_eq!(hash.len(), 64);
+    assert!(hash.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()));
+    // Case 0: threads are shared out per invocation and do not change the transcript
+    let mut threads = params();
+    threads.decode.threads = 6;
+    assert_eq!(settings_sha256(&threads), hash);
+    // Case 1: anything else does
+    let mut beam = params();
+    beam.decode.beam_size += 1;
+    assert_ne!(settings_sha256(&beam), hash);
+    let prompt = TranscriptParams { prompt: None, ..params() };
+    assert_ne!(settings_sha256(&prompt), hash);
+    let language = TranscriptParams { language: "es".to_string(), ..params() };
+    assert_ne!(settings_sha256(&language), hash);
+}
+
+#[test]
+fn stamp_metadata() {
+    assert_eq!(normalize_etag("\"d9221b8cfeaae16e0d50dd70369e15e1\""), "d9221b8cfeaae16e0d50dd70369e15e1");
+    assert_eq!(normalize_etag("d9221b8cfeaae16e0d50dd70369e15e1-3"), "d9221b8cfeaae16e0d50dd70369e15e1-3");
+    // Case 0: round trip through S3 user metadata, the listing ETag matching the unquoted one
+    let stamp = Stamp::new("\"d9221b8cfeaae16e0d50dd70369e15e1\"", "ggml-base.en", &params());
+    let metadata = stamp.metadata();
+    assert_eq!(metadata["source-etag"], "d9221b8cfeaae16e0d50dd70369e15e1");
+    assert_eq!(Stamp::from_metadata(&metadata), Some(stamp.clone()));
+    // Case 1: another upload of the video, or another model, is not current
+    assert_ne!(Stamp::new("\"0cc175b9c0f1b6a831c399e269772661\"", "ggml-base.en", &params()), stamp);
+    assert_ne!(Stamp::new("\"d9221b8cfeaae16e0d50dd70369e15e1\"", "ggml-small", &params()), stamp);
+    // Case 2: transcripts uploaded without a stamp
+    assert_eq!(Stamp::from_metadata(&HashMap::new()), None);
+    let partial = HashMap::from([("model".to_string(), "ggml-base.en".to_string())]);
+    assert_eq!(Stamp::from_metadata(&partial), None);
+}
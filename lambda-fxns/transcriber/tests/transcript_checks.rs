@@ -0,0 +1,77 @@
+use transcriber::pipeline::parse_whisper_json;
+use transcriber::transcript::{words, Token, Transcript, TRANSCRIPT_VERSION};
+
+fn token(text: &str, start_ms: u64, end_ms: u64, probability: f32) -> Token {
+    Token {
+        id: 0,
+        start_ms,
+        end_ms,
+        text: text.to_string(),
+        probability,
+    }
+}
+
+#[test]
+fn parse_whisper_output() {
+    let transcript = parse_whisper_json(include_bytes!("fixtures/whisper.json"), "week1/lesson1/video0.mp4", "ggml-base.en").unwrap();
+    assert_eq!(transcript.version, TRANSCRIPT_VERSION);
+    assert_eq!(transcript.source_key, "week1/lesson1/video0.mp4");
+    assert_eq!(transcript.model, "ggml-base.en");
+    assert_eq!(transcript.language, "en");
+    assert_eq!(transcript.segments.len(), 2);
+    let segment = &transcript.segments[0];
+    assert_eq!((segment.start_ms, segment.end_ms), (0, 4200));
+    assert_eq!(segment.text, "Welcome to week one of the course.");
+    // Control tokens are dropped, sub-word tokens and punctuation are joined into words
+    let text: Vec<&str> = segment.words.iter().map(|w| w.text.as_str()).collect();
+    assert_eq!(text, vec!["Welcome", "to", "week", "one", "of", "the", "course."]);
+    let course = segment.words.last().unwrap();
+    assert_eq!((course.start_ms, course.end_ms), (2100, 3200));
+    assert_eq!(course.tokens.len(), 3);
+    assert!((course.probability - 0.766).abs() < 0.001);
+    assert_eq!(course.tokens[0].id, 1093);
+    // Segments without token output still parse
+    assert!(transcript.segments[1].words.is_empty());
+    // Invalid UTF-8 from split multi-byte tokens does not fail the video
+    let json = b"{\"transcription\":[{\"offsets\":{\"from\":0,\"to\":1000},\"text\":\" caf\xc3\"}]}";
+    assert_eq!(parse_whisper_json(json, "video0.mp4", "ggml-base.en").unwrap().segments[0].text, "caf\u{fffd}");
+    assert!(parse_whisper_json(b"[00:00:00.000 --> 00:00:01.000]  hello", "video0.mp4", "ggml-base.en").is_err());
+    assert_eq!(transcript.text(), "Welcome to week one of the course.\nToday we will cover ownership, borrowing and lifetimes in Rust, with a few examples.\n");
+}
+
+#[test]
+fn group_tokens_into_words() {
+    let grouped = words(vec![
+        token(" Hel", 0, 100, 0.5),
+        token("lo", 100, 200, 1.0),
+        token(",", 200, 250, 1.0),
+        token(" ", 250, 260, 0.1),
+        token(" world", 300, 600, 0.9),
+    ]);
+    // Whitespace-only tokens are dropped
+    assert_eq!(grouped.len(), 2);
+    assert_eq!(grouped[0].text, "Hello,");
+    assert_eq!((grouped[0].start_ms, grouped[0].end_ms), (0, 250));
+    assert_eq!(grouped[1].text, "world");
+    assert!((grouped[0].probability - 2.5 / 3.0).abs() < 0.001);
+    // A leading token without a space still starts the first word
+    assert_eq!(words(vec![token("Hi", 0, 100, 1.0)])[0].text, "Hi");
+}
+
+#[test]
+fn round_trip_json() {
+    let transcript = parse_whisper_json(include_bytes!("fixtures/whisper.json"), "video0.mp4", "ggml-base.en").unwrap();
+    let json = serde_json::to_string(&transcript).unwrap();
+    let parsed: Transcript = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, transcript);
+    // Downstream tools can rely on the field names
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["version"], 1);
+    assert_eq!(value["segments"][0]["words"][0]["tokens"][0]["text"], " Welcome");
+    assert_eq!(value["params"]["decode"]["beam_size"], 5);
+    // Transcripts written before params were recorded still parse
+    let mut value = value;
+    value.as_object_mut().unwrap().remove("params");
+    let parsed: Transcript = serde_json::from_value(value).unwrap();
+    assert_eq!(parsed.params.language, "");
+}
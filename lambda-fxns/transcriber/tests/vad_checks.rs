@@ -0,0 +1,88 @@
+use transcriber::transcript::{Segment, Transcript, TranscriptParams};
+use transcriber::vad::{pcm_samples, samples_ms, speech_regions, splice, wav, Span, Timeline, Vad};
+
+// 16kHz audio from (ms, speech) stretches. Speech is a 440Hz tone around -12 dBFS, silence a faint hiss
+fn audio(stretches: &[(u64, bool)]) -> Vec<i16> {
+    let mut samples: Vec<i16> = vec![];
+    for (ms, speech) in stretches {
+        for i in 0..(*ms * 16) {
+            let sample = match speech {
+                true => ((i as f64 * 440.0 * std::f64::consts::TAU / 16000.0).sin() * 8000.0) as i16,
+                false => [3, -3][i as usize % 2],
+            };
+            samples.push(sample);
+        }
+    }
+    samples
+}
+
+#[test]
+fn find_speech() {
+    let vad = Vad::default();
+    // Case 0: intro silence and an outro are left out, padded by pad_ms
+    let samples = audio(&[(5010, false), (3000, true), (4980, false)]);
+    assert_eq!(samples_ms(samples.len()), 12990);
+    assert_eq!(speech_regions(&samples, &vad), vec![(4810, 8210)]);
+    // Case 1: pauses shorter than min_silence_ms stay in
+    let samples = audio(&[(990, true), (600, false), (990, true), (3000, false), (990, true)]);
+    assert_eq!(speech_regions(&samples, &vad), vec![(0, 2780), (5380, 6570)]);
+    // Case 2: clicks shorter than min_speech_ms are not speech
+    let samples = audio(&[(3000, false), (90, true), (3000, false)]);
+    assert!(speech_regions(&samples, &vad).is_empty());
+    assert!(speech_regions(&[], &vad).is_empty());
+}
+
+#[test]
+fn splice_speech() {
+    // Case 0: speech regions are cut out and joined
+    let samples = audio(&[(5010, false), (3000, true), (3000, false), (1020, true), (990, false)]);
+    let regions = vec![(4800, 8220), (10800, 12210)];
+    let (spliced, timeline) = splice(&samples, &regions);
+    // 300ms of silence between the regions
+    assert_eq!(samples_ms(spliced.len()), 3420 + 300 + 1410);
+    assert_eq!(
+        timeline.spans,
+        vec![
+            Span { spliced_ms: 0, original_ms: 4800, len_ms: 3420 },
+            Span { spliced_ms: 3720, original_ms: 10800, len_ms: 1410 },
+        ]
+    );
+    assert_eq!(&spliced[..16], &samples[4800 * 16..4801 * 16]);
+    // Case 1: whisper reads it as 16kHz mono 16-bit WAV
+    let bytes = wav(&spliced);
+    assert_eq!(&bytes[..4], b"RIFF");
+    assert_eq!(&bytes[8..16], b"WAVEfmt ");
+    assert_eq!(u32::from_le_bytes(bytes[24..28].try_into().unwrap()), 16000);
+    assert_eq!(&bytes[36..40], b"data");
+    assert_eq!(pcm_samples(&bytes[44..]), spliced);
+}
+
+#[test]
+fn remap_timestamps() {
+    let timeline = Timeline {
+        spans: vec![
+            Span { spliced_ms: 0, original_ms: 4800, len_ms: 3420 },
+            Span { spliced_ms: 3720, original_ms: 10800, len_ms: 1410 },
+        ],
+    };
+    // Case 0: times inside a span shift by its offset
+    assert_eq!(timeline.original_ms(0), 4800);
+    assert_eq!(timeline.original_ms(1000), 5800);
+    // Case 1: the gap between spans lands at the end of the span before
+    assert_eq!(timeline.original_ms(3500), 8220);
+    assert_eq!(timeline.original_ms(3720), 10800);
+    assert_eq!(timeline.original_ms(4000), 11080);
+    // Case 2: segments end up on the original timeline
+    let segment = |start_ms, end_ms| Segment { start_ms, end_ms, text: "Ownership".to_string(), words: vec![] };
+    let mut transcript = Transcript {
+        version: 1,
+        source_key: "week1/video0.mp4".to_string(),
+        model: "ggml-base.en".to_string(),
+        language: "en".to_string(),
+        params: TranscriptParams::default(),
+        segments: vec![segment(200, 3400), segment(3700, 5000)],
+    };
+    timeline.remap(&mut transcript);
+    let times: Vec<(u64, u64)> = transcript.segments.iter().map(|s| (s.start_ms, s.end_ms)).collect();
+    assert_eq!(times, vec![(5000, 8200), (8220, 12080)]);
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use transcriber::pipeline::parse_whisper_json;
+use transcriber::vocabulary::{vocabulary_candidates, Corrections, Vocabularies, Vocabulary, VocabularyStore};
+
+fn corrections(pairs: &[(&str, &str)]) -> Corrections {
+    let map: BTreeMap<String, String> = pairs.iter().map(|(a, b)| (a.to_string(), b.to_string())).collect();
+    Corrections::new(&map)
+}
+
+#[test]
+fn build_prompt() {
+    let vocabulary: Vocabulary = serde_json::from_str(r#"{"terms": ["Rust", " tokio ", "", "Step Functions"]}"#).unwrap();
+    assert_eq!(vocabulary.prompt().unwrap(), "Rust, tokio, Step Functions");
+    let vocabulary: Vocabulary = serde_json::from_str(r#"{"prompt": " A lecture on Rust and AWS Lambda. ", "terms": ["Rust"]}"#).unwrap();
+    assert_eq!(vocabulary.prompt().unwrap(), "A lecture on Rust and AWS Lambda.");
+    assert_eq!(Vocabulary::default().prompt(), None);
+    // Long prompts are cut at a word boundary
+    let terms: Vec<String> = (0..200).map(|i| format!("term{}", i)).collect();
+    let vocabulary = Vocabulary { terms, ..Default::default() };
+    let prompt = vocabulary.prompt().unwrap();
+    assert!(prompt.len() <= 600, "{}", prompt.len());
+    assert!(prompt.starts_with("term0, term1,") && !prompt.ends_with(','));
+    assert!(vocabulary.terms.iter().any(|t| prompt.ends_with(t.as_str())));
+}
+
+#[test]
+fn correct_terms() {
+    let fixes = corrections(&[("rust", "Rust"), ("step functions", "Step Functions"), ("functions", "functions()"), ("c++", "C++"), ("lamda", "Lambda")]);
+    // Whole words only, and case-sensitive
+    assert_eq!(fixes.apply("rust is not trust or Rusty, rust."), "Rust is not trust or Rusty, Rust.");
+    assert_eq!(fixes.apply("RUST and Rust"), "RUST and Rust");
+    // Phrases win over the words inside them and replacements are not corrected again
+    assert_eq!(fixes.apply("step functions call functions"), "Step Functions call functions()");
+    assert_eq!(fixes.apply("c++ and aws lamda"), "C++ and aws Lambda");
+    // Replacement text is literal
+    assert_eq!(corrections(&[("cost", "$5")]).apply("the cost"), "the $5");
+    assert!(corrections(&[]).is_empty());
+    assert_eq!(corrections(&[]).apply("rust"), "rust");
+}
+
+#[test]
+fn correct_transcript() {
+    let mut transcript = parse_whisper_json(include_bytes!("fixtures/whisper.json"), "week1/lesson1/video0.mp4", "ggml-base.en").unwrap();
+    corrections(&[("week", "Week"), ("Rust", "Rust 🦀")]).apply_transcript(&mut transcript);
+    assert_eq!(transcript.segments[0].text, "Welcome to Week one of the course.");
+    assert_eq!(transcript.segments[0].words[2].text, "Week");
+    // Raw tokens keep what whisper produced
+    assert_eq!(transcript.segments[0].words[2].tokens[0].text, " week");
+    assert!(transcript.segments[1].text.contains("lifetimes in Rust 🦀, with"));
+}
+
+// Vocabularies held in memory in place of the video bucket, counting the reads
+#[derive(Default)]
+struct MemoryStore {
+    vocabularies: BTreeMap<String, Vocabulary>,
+    reads: Mutex<Vec<String>>,
+}
+
+impl VocabularyStore for MemoryStore {
+    async fn load(&self, key: &str) -> Result<Option<Vocabulary>, String> {
+        self.reads.lock().unwrap().push(key.to_string());
+        match key {
+            "course-b/vocabulary.json" => Err("expected value at line 1 column 1".to_string()),
+            key => Ok(self.vocabularies.get(key).cloned()),
+        }
+    }
+}
+
+fn terms(terms: &[&str]) -> Vocabulary {
+    Vocabulary { terms: terms.iter().map(|t| t.to_string()).collect(), ..Default::default() }
+}
+
+#[test]
+fn find_vocabulary_candidates() {
+    assert_eq!(
+        vocabulary_candidates("course-a/week1/lesson1/video0.mp4"),
+        vec!["course-a/week1/lesson1/vocabulary.json", "course-a/week1/vocabulary.json", "course-a/vocabulary.json", "vocabulary.json"]
+    );
+    assert_eq!(vocabulary_candidates("video0.mp4"), vec!["vocabulary.json"]);
+}
+
+#[tokio::test]
+async fn resolve_vocabulary_per_video() {
+    let store = MemoryStore {
+        vocabularies: BTreeMap::from([
+            ("course-a/week1/lesson1/vocabulary.json".to_string(), terms(&["borrow checker"])),
+            ("course-a/week1/vocabulary.json".to_string(), terms(&["Rust"])),
+        ]),
+        ..Default::default()
+    };
+    // Case 0: Two lessons in one batch each get their own nearest vocabulary, as they would batched apart
+    let mut vocabularies = Vocabularies::default();
+    for key in ["course-a/week1/lesson1/video0.mp4", "course-a/week1/lesson2/video0.mp4", "course-a/week1/lesson1/video1.mp4", "course-a/week1/lesson2/video1.mp4"] {
+        vocabularies.resolve(&store, key).await.unwrap();
+    }
+    let (key, vocabulary) = vocabularies.get("course-a/week1/lesson1/video1.mp4").unwrap();
+    assert_eq!((key, vocabulary.prompt().unwrap().as_str()), ("course-a/week1/lesson1/vocabulary.json", "borrow checker"));
+    let (key, vocabulary) = vocabularies.get("course-a/week1/lesson2/video0.mp4").unwrap();
+    assert_eq!((key, vocabulary.prompt().unwrap().as_str()), ("course-a/week1/vocabulary.json", "Rust"));
+    let mut alone = Vocabularies::default();
+    alone.resolve(&store, "course-a/week1/lesson2/video0.mp4").await.unwrap();
+    assert_eq!(alone.get("course-a/week1/lesson2/video0.mp4").map(|(k, _)| k), Some("course-a/week1/vocabulary.json"));
+
+    // Case 1: Within a batch each directory is looked up once, and each vocabulary key read once. The last two reads are
+    // the batch of lesson2 alone
+    assert_eq!(
+        *store.reads.lock().unwrap(),
+        vec!["course-a/week1/lesson1/vocabulary.json", "course-a/week1/lesson2/vocabulary.json", "course-a/week1/vocabulary.json", "course-a/week1/lesson2/vocabulary.json", "course-a/week1/vocabulary.json"]
+    );
+
+    // Case 2: Videos with no vocabulary above them, or a broken one, go without
+    assert!(vocabularies.resolve(&store, "course-c/video0.mp4").await.is_ok());
+    assert!(vocabularies.get("course-c/video0.mp4").is_none());
+    assert!(vocabularies.resolve(&store, "course-b/week1/video0.mp4").await.is_err());
+    assert!(vocabularies.get("course-b/week1/video0.mp4").is_none());
+    assert!(vocabularies.resolve(&store, "course-b/week1/video1.mp4").await.is_ok());
+
+    // Case 3: A vocabulary named by the run options is used for every video
+    let fixed = Vocabularies::fixed("shared/vocabulary.json".to_string(), terms(&["Lambda"]));
+    assert_eq!(fixed.get("course-a/week1/lesson1/video0.mp4").map(|(k, _)| k), Some("shared/vocabulary.json"));
+}
@@ -5,6 +5,9 @@ use regex::Regex;
 use std::path::{Path, PathBuf};
 use std::process;
 
+// Marker uploaded once all videos in a batch have been uploaded
+pub const DONE_FILE: &str = "done.txt";
+
 // Create S3 client
 pub async fn init_s3client() -> Result<Client, Error> {
     let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
@@ -86,6 +89,36 @@ pub async fn extract_key(path: &Path) -> Option<String> {
     Some(key)
 }
 
+// Prepend an optional key prefix (i.e. course name) to a video key
+pub fn prefixed_key(prefix: Option<&str>, key: &str) -> String {
+    match prefix
+        .map(|p| p.trim_matches('/'))
+        .filter(|p| !p.is_empty())
+    {
+        Some(p) => format!("{}/{}", p, key),
+        None => key.to_string(),
+    }
+}
+
+// Done marker key scoped to the deepest directory shared by all uploaded keys
+// i.e. [week1/lesson1/video0.mp4, week1/lesson1/video1.mp4] --> week1/lesson1/done.txt
+pub fn marker_key(keys: &[String]) -> String {
+    let mut common: Vec<&str> = match keys.first() {
+        Some(first) => first.split('/').collect(),
+        None => vec![],
+    };
+    // drop the file name
+    common.pop();
+    for key in keys.iter().skip(1) {
+        let dirs: Vec<&str> = key.split('/').collect();
+        let dirs = &dirs[..dirs.len() - 1];
+        let shared = common.iter().zip(dirs).take_while(|(a, b)| a == b).count();
+        common.truncate(shared);
+    }
+    common.push(DONE_FILE);
+    common.join("/")
+}
+
 // Put object in bucket
 pub async fn upload_object(
     client: &Client,
@@ -4,17 +4,23 @@ use std::error::Error;
 use std::fs::File;
 use std::path::Path;
 use std::process;
-use transcribe::{extract_key, init_s3client, upload_object, validate_config, validate_path};
+use transcribe::{
+    extract_key, init_s3client, marker_key, prefixed_key, upload_object, validate_config,
+    validate_path, DONE_FILE,
+};
 
 #[derive(Parser, Default, Debug)]
 #[clap(
     version = "1.0",
     author = "Kahlia Hogg",
     about = "Transcriber",
-    after_help = "Example: ./transcribe /path/to/vid_dir"
+    after_help = "Example: ./transcribe /path/to/vid_dir --prefix course-a"
 )]
 struct Args {
     vid_dir: String,
+    /// Key prefix to upload under i.e. course name
+    #[clap(long)]
+    prefix: Option<String>,
 }
 
 #[tokio::main]
@@ -36,6 +42,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         }
     }
     // get all videos in vids_dir and subdirs
+    let mut uploaded: Vec<String> = vec![];
     let glob_pattern = format!("{}/**/video*.mp4", args.vid_dir);
     for entry in glob(&glob_pattern).expect("ERROR: Failed to glob *.mp4 files") {
         match entry {
@@ -50,7 +57,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 }
                 // Extract key from path
                 let key = match extract_key(&vid_path).await {
-                    Some(key) => key,
+                    Some(key) => prefixed_key(args.prefix.as_deref(), &key),
                     None => {
                         println!("ERROR: Failed to extract key from {}", vid_path.display());
                         break;
@@ -58,7 +65,10 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 };
                 // Send to S3
                 match upload_object(&s3client, &vid_bucket, &vid_path, &key).await {
-                    Ok(_) => println!("SUCCESS: uploaded {}", vid_path.display()),
+                    Ok(_) => {
+                        println!("SUCCESS: uploaded {}", vid_path.display());
+                        uploaded.push(key);
+                    }
                     Err(e) => {
                         println!("ERROR: Failed to upload {}. {}", vid_path.display(), e);
                     }
@@ -67,11 +77,20 @@ async fn main() -> Result<(), Box<dyn Error>> {
             Err(e) => println!("Failed to read glob entry. {}", e),
         }
     }
-    // create & upload done file
-    let done_path = Path::new("done.txt");
+    if uploaded.is_empty() {
+        println!("ERROR: No videos uploaded from {}", vid_dir.display());
+        process::exit(1);
+    }
+    // create & upload done file scoped to the uploaded week/lesson/course
+    let done_key = marker_key(&uploaded);
+    let done_path = Path::new(DONE_FILE);
     let _file = File::create(done_path);
-    match upload_object(&s3client, &vid_bucket, done_path, "done.txt").await {
-        Ok(_) => println!("SUCCESS: Upload complete for {}", vid_dir.display()),
+    match upload_object(&s3client, &vid_bucket, done_path, &done_key).await {
+        Ok(_) => println!(
+            "SUCCESS: Upload complete for {} ({})",
+            vid_dir.display(),
+            done_key
+        ),
         Err(e) => println!("ERROR: Failed to upload done file. {}", e),
     }
 
@@ -0,0 +1,49 @@
+use transcribe::{marker_key, prefixed_key};
+
+fn keys(keys: &[&str]) -> Vec<String> {
+    keys.iter().map(|k| k.to_string()).collect()
+}
+
+#[test]
+fn prefix_upload_keys() {
+    assert_eq!(
+        prefixed_key(None, "week1/lesson1/video0.mp4"),
+        "week1/lesson1/video0.mp4"
+    );
+    assert_eq!(
+        prefixed_key(Some(""), "week1/lesson1/video0.mp4"),
+        "week1/lesson1/video0.mp4"
+    );
+    assert_eq!(
+        prefixed_key(Some("course-a"), "week1/lesson1/video0.mp4"),
+        "course-a/week1/lesson1/video0.mp4"
+    );
+    assert_eq!(
+        prefixed_key(Some("/course-a/"), "week1/lesson1/video0.mp4"),
+        "course-a/week1/lesson1/video0.mp4"
+    );
+}
+
+#[test]
+fn scope_done_markers() {
+    // Case 0: Single lesson
+    let lesson = keys(&["week1/lesson1/video0.mp4", "week1/lesson1/video1.mp4"]);
+    assert_eq!(marker_key(&lesson), "week1/lesson1/done.txt");
+
+    // Case 1: Single week
+    let week = keys(&["week1/lesson1/video0.mp4", "week1/lesson2/video0.mp4"]);
+    assert_eq!(marker_key(&week), "week1/done.txt");
+
+    // Case 2: Whole course with and without prefix
+    let course = keys(&["week1/lesson1/video0.mp4", "week2/lesson1/video0.mp4"]);
+    assert_eq!(marker_key(&course), "done.txt");
+    let course = keys(&[
+        "course-a/week1/lesson1/video0.mp4",
+        "course-a/week2/lesson1/video0.mp4",
+    ]);
+    assert_eq!(marker_key(&course), "course-a/done.txt");
+
+    // Case 3: Directory names sharing a string prefix are not merged
+    let lessons = keys(&["week1/lesson1/video0.mp4", "week1/lesson10/video0.mp4"]);
+    assert_eq!(marker_key(&lessons), "week1/done.txt");
+}